@@ -4,9 +4,11 @@ extern crate typed_arena;
 
 use criterion::{Criterion, BenchmarkId};
 
+#[allow(dead_code)]
 #[derive(Default)]
 struct Small(usize);
 
+#[allow(dead_code)]
 #[derive(Default)]
 struct Big([usize; 32]);
 
@@ -18,6 +20,14 @@ fn allocate<T: Default>(n: usize) {
     }
 }
 
+fn allocate_within_capacity<T: Default>(n: usize) {
+    let arena = typed_arena::Arena::with_capacity(n);
+    for _ in 0..n {
+        let val: &mut T = arena.alloc(Default::default());
+        criterion::black_box(val);
+    }
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("allocate");
     for n in 1..5 {
@@ -33,6 +43,11 @@ fn criterion_benchmark(c: &mut Criterion) {
             &n,
             |b, &n| b.iter(|| allocate::<Big>(n)),
         );
+        group.bench_with_input(
+            BenchmarkId::new("allocate-small-within-capacity", n),
+            &n,
+            |b, &n| b.iter(|| allocate_within_capacity::<Small>(n)),
+        );
     }
 }
 
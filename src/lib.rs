@@ -63,13 +63,21 @@ extern crate alloc;
 #[cfg(any(feature = "std", test))]
 extern crate core;
 
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::vec::{self, Vec};
+#[cfg(feature = "std")]
+use std::vec;
 
-use core::cell::RefCell;
+use core::cell::{Cell, RefCell, RefMut};
 use core::cmp;
-use core::iter;
+use core::fmt;
+use core::iter::{self, FromIterator};
 use core::mem;
+use core::ops::{Deref, DerefMut, Range};
+use core::ptr;
 use core::slice;
 use core::str;
 
@@ -99,6 +107,17 @@ const MIN_CAPACITY: usize = 1;
 /// let vegeta = monsters.alloc(Monster { level: 9001 });
 /// assert!(vegeta.level > 9000);
 /// ```
+///
+/// ## Reading values
+///
+/// [`alloc`](struct.Arena.html#method.alloc) and the other allocation methods hand out `&mut T`
+/// through `&self`, so that new values can be allocated while earlier ones are still in use.
+/// Reading the values through `&self` could then create a shared reference to a value while one
+/// of those mutable references is still alive, which is unsound. So every method that reads
+/// values out of the arena in place, like [`iter_mut`](struct.Arena.html#method.iter_mut) or
+/// [`as_mut_slice`](struct.Arena.html#method.as_mut_slice), takes `&mut self`, which ends all
+/// borrows from earlier allocations. For the same reason, `Arena` doesn't implement `Clone` or
+/// `AsRef<[T]>`.
 pub struct Arena<T> {
     chunks: RefCell<ChunkList<T>>,
 }
@@ -170,6 +189,90 @@ impl<T> Arena<T> {
         res + chunks.current.len()
     }
 
+    /// Returns `true` if no values are allocated in the arena.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    /// assert!(arena.is_empty());
+    ///
+    /// arena.alloc(1);
+    /// assert!(!arena.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        let chunks = self.chunks.borrow();
+        // Earlier chunks may have been emptied by `truncate` or `retain`.
+        chunks.current.is_empty() && chunks.rest.iter().all(|vec| vec.is_empty())
+    }
+
+    /// Returns the number of values the arena can hold before it needs to allocate a new chunk
+    /// of memory.
+    ///
+    /// This is the number of values already allocated plus the spare capacity of the current
+    /// chunk. Up to `capacity() - len()` more values can be allocated without allocating memory,
+    /// for example with [`push_within_capacity`][Arena::push_within_capacity].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let arena = Arena::with_capacity(4);
+    /// arena.alloc(1);
+    ///
+    /// assert_eq!(arena.len(), 1);
+    /// assert_eq!(arena.capacity(), 4);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.len() + self.remaining_capacity()
+    }
+
+    /// Returns the number of values that can still be allocated before the arena needs to
+    /// allocate a new chunk of memory, which is `capacity() - len()`.
+    ///
+    /// ## Example
+    ///
+    /// Moving on to a new arena instead of letting one grow past a fixed size:
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arenas = vec![Arena::with_capacity(4)];
+    /// for batch in vec![vec![1, 2, 3], vec![4, 5], vec![6]] {
+    ///     if arenas.last().unwrap().remaining_capacity() < batch.len() {
+    ///         arenas.push(Arena::with_capacity(4));
+    ///     }
+    ///     arenas.last().unwrap().alloc_extend(batch);
+    /// }
+    ///
+    /// let lens: Vec<_> = arenas.iter().map(|arena| arena.len()).collect();
+    /// assert_eq!(lens, vec![3, 3]);
+    /// ```
+    pub fn remaining_capacity(&self) -> usize {
+        let chunks = self.chunks.borrow();
+        chunks.current.capacity() - chunks.current.len()
+    }
+
+    /// Returns `true` if the next allocation needs a new chunk of memory, which is when
+    /// `remaining_capacity() == 0`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let arena = Arena::with_capacity(2);
+    /// arena.alloc_extend(vec![1, 2]);
+    ///
+    /// assert!(arena.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        self.remaining_capacity() == 0
+    }
+
     /// Allocates a value in the arena, and returns a mutable reference
     /// to that value.
     ///
@@ -182,13 +285,39 @@ impl<T> Arena<T> {
     /// let x = arena.alloc(42);
     /// assert_eq!(*x, 42);
     /// ```
-    #[inline]
+    #[cfg_attr(not(all(feature = "debug-noinline", debug_assertions)), inline)]
+    #[cfg_attr(all(feature = "debug-noinline", debug_assertions), inline(never))]
+    #[allow(clippy::mut_from_ref)]
     pub fn alloc(&self, value: T) -> &mut T {
         self.alloc_fast_path(value)
             .unwrap_or_else(|value| self.alloc_slow_path(value))
     }
 
+    /// Allocates a value in the arena only if it fits into the already allocated memory, and
+    /// returns a mutable reference to that value.
+    ///
+    /// Unlike [`alloc`][Arena::alloc], this never allocates a new chunk of memory. If there is no
+    /// spare capacity left, the value is handed back as the error, like
+    /// `Vec::push_within_capacity` does.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let arena = Arena::with_capacity(1);
+    /// assert_eq!(arena.push_within_capacity(1), Ok(&mut 1));
+    /// assert_eq!(arena.push_within_capacity(2), Err(2));
+    /// ```
     #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub fn push_within_capacity(&self, value: T) -> Result<&mut T, T> {
+        self.alloc_fast_path(value)
+    }
+
+    #[cfg_attr(not(all(feature = "debug-noinline", debug_assertions)), inline)]
+    #[cfg_attr(all(feature = "debug-noinline", debug_assertions), inline(never))]
+    #[allow(clippy::mut_from_ref)]
     fn alloc_fast_path(&self, value: T) -> Result<&mut T, T> {
         let mut chunks = self.chunks.borrow_mut();
         let len = chunks.current.len();
@@ -203,6 +332,10 @@ impl<T> Arena<T> {
         }
     }
 
+    // Only taken once per chunk, so keep it out of line and out of the way of the fast path.
+    #[inline(never)]
+    #[cold]
+    #[allow(clippy::mut_from_ref)]
     fn alloc_slow_path(&self, value: T) -> &mut T {
         &mut self.alloc_extend(iter::once(value))[0]
     }
@@ -219,6 +352,7 @@ impl<T> Arena<T> {
     /// let abc = arena.alloc_extend("abcdefg".chars().take(3));
     /// assert_eq!(abc, ['a', 'b', 'c']);
     /// ```
+    #[allow(clippy::mut_from_ref)]
     pub fn alloc_extend<I>(&self, iterable: I) -> &mut [T]
     where
         I: IntoIterator<Item = T>,
@@ -263,14 +397,105 @@ impl<T> Arena<T> {
                 i += 1;
             }
         }
-        let new_slice_ref = &mut chunks.current[next_item_index..];
+        let new_len = chunks.current.len() - next_item_index;
 
         // Extend the lifetime from that of `chunks_borrow` to that of `self`.
         // This is OK because we’re careful to never move items
         // by never pushing to inner `Vec`s beyond their initial capacity.
         // The returned reference is unique (`&mut`):
         // the `Arena` never gives away references to existing items.
-        unsafe { mem::transmute::<&mut [T], &mut [T]>(new_slice_ref) }
+        // Go through a pointer rather than `Vec::deref_mut`, which overlaps
+        // other references we have already handed out!
+        unsafe {
+            let start = chunks.current.as_mut_ptr().add(next_item_index);
+            slice::from_raw_parts_mut(start, new_len)
+        }
+    }
+
+    /// Allocates the contents of an iterator like [`alloc_extend`][Arena::alloc_extend], and also
+    /// returns the allocation index of the first of the new values.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(0..3);
+    ///
+    /// let (start, values) = arena.alloc_extend_indexed(vec![10, 11]);
+    /// assert_eq!(start, 3);
+    /// assert_eq!(values, [10, 11]);
+    /// assert_eq!(arena.get_range_mut(start..start + 2), Some(&mut [10, 11][..]));
+    /// ```
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_extend_indexed<I>(&self, iterable: I) -> (usize, &mut [T])
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let start = self.len();
+        (start, self.alloc_extend(iterable))
+    }
+
+    /// Allocates a copy of a slice and returns a mutable reference to it.
+    ///
+    /// Since `T` is `Copy`, the values are copied all at once, rather than allocated one by one
+    /// like with [`alloc_extend`][Arena::alloc_extend].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    /// let copy = arena.alloc_slice_copy(&[1, 2, 3]);
+    /// copy[0] = 10;
+    ///
+    /// assert_eq!(copy, [10, 2, 3]);
+    /// ```
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_copy(&self, src: &[T]) -> &mut [T]
+    where
+        T: Copy,
+    {
+        unsafe {
+            let buffer = self.alloc_uninitialized(src.len());
+            let start = buffer.as_mut_ptr() as *mut T;
+            // Nothing can panic before the buffer is fully initialized.
+            ptr::copy_nonoverlapping(src.as_ptr(), start, src.len());
+            slice::from_raw_parts_mut(start, src.len())
+        }
+    }
+
+    /// Allocates clones of the values in a slice and returns a mutable reference to them.
+    ///
+    /// This is [`alloc_slice_copy`][Arena::alloc_slice_copy] for values that are `Clone` but not
+    /// `Copy`. Like with [`alloc_extend`][Arena::alloc_extend], the clones are contiguous, even
+    /// if they don't fit into the current chunk.
+    ///
+    /// If cloning panics, the clones made so far stay in the arena and are dropped along with it.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    /// let names = [String::from("a"), String::from("b")];
+    /// let clones = arena.alloc_slice_clone(&names);
+    /// clones[1].push('c');
+    ///
+    /// assert_eq!(clones, ["a", "bc"]);
+    /// assert_eq!(names, ["a", "b"]);
+    /// ```
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_clone(&self, src: &[T]) -> &mut [T]
+    where
+        T: Clone,
+    {
+        self.alloc_extend(src.iter().cloned())
     }
 
     /// Allocates space for a given number of values, but doesn't initialize it.
@@ -334,11 +559,11 @@ impl<T> Arena<T> {
     /// unsafe {
     ///     // Perform initialization before we claim the memory.
     ///     let uninitialized = arena.uninitialized_array();
-    ///     assert!((*uninitialized).len() >= COUNT); // Ensured by the reserve_extend
-    ///     for elem in &mut (*uninitialized)[..COUNT] {
+    ///     assert!((&*uninitialized).len() >= COUNT); // Ensured by the reserve_extend
+    ///     for elem in &mut (&mut *uninitialized)[..COUNT] {
     ///         ptr::write(elem.as_mut_ptr(), "Hello".to_owned());
     ///     }
-    ///     let addr = (*uninitialized).as_ptr() as usize;
+    ///     let addr = (&*uninitialized).as_ptr() as usize;
     ///
     ///     // The alloc_uninitialized returns the same memory, but "confirms" its allocation.
     ///     slice = transmute_uninit(arena.alloc_uninitialized(COUNT));
@@ -346,6 +571,7 @@ impl<T> Arena<T> {
     ///     assert_eq!(slice, &["Hello".to_owned(), "Hello".to_owned()]);
     /// }
     /// ```
+    #[allow(clippy::mut_from_ref)]
     pub unsafe fn alloc_uninitialized(&self, num: usize) -> &mut [MaybeUninit<T>] {
         let mut chunks = self.chunks.borrow_mut();
 
@@ -362,7 +588,7 @@ impl<T> Arena<T> {
         chunks.current.set_len(next_item_index + num);
 
         // Go through pointers, to make sure we never create a reference to uninitialized T.
-        let start = chunks.current.as_mut_ptr().offset(next_item_index as isize);
+        let start = chunks.current.as_mut_ptr().add(next_item_index);
         let start_uninit = start as *mut MaybeUninit<T>;
         slice::from_raw_parts_mut(start_uninit, num)
     }
@@ -393,18 +619,59 @@ impl<T> Arena<T> {
     /// [`alloc_extend`][Arena::alloc_extend] which is why the method is safe.
     ///
     /// It returns a raw pointer to avoid creating multiple mutable references to the same place.
-    /// It is up to the caller not to dereference it after any of the `alloc_` methods are called.
+    /// It is up to the caller not to dereference it after any of the `alloc_` methods, or any
+    /// other method that allocates values, is called. The same goes for every method taking
+    /// `&mut self`: [`as_mut_slice`][Arena::as_mut_slice] may move the unused space elsewhere,
+    /// and [`shrink_to_fit`][Arena::shrink_to_fit], [`clear`][Arena::clear] and
+    /// [`drain_into`][Arena::drain_into] may free or reallocate it.
     pub fn uninitialized_array(&self) -> *mut [MaybeUninit<T>] {
         let mut chunks = self.chunks.borrow_mut();
         let len = chunks.current.capacity() - chunks.current.len();
         let next_item_index = chunks.current.len();
 
         unsafe {
-        // Go through pointers, to make sure we never create a reference to uninitialized T.
-            let start = chunks.current.as_mut_ptr().offset(next_item_index as isize);
+            // Go through pointers, to make sure we never create a reference to uninitialized T.
+            // Don't create a reference to the unused space either, so the returned pointer
+            // isn't invalidated by the arena's own writes to it.
+            let start = chunks.current.as_mut_ptr().add(next_item_index);
             let start_uninit = start as *mut MaybeUninit<T>;
-            slice::from_raw_parts_mut(start_uninit, len) as *mut _
+            ptr::slice_from_raw_parts_mut(start_uninit, len)
+        }
+    }
+
+    /// Reserves space for one value and returns a guard for constructing it in place.
+    ///
+    /// The value is only considered allocated once [`AllocGuard::commit`] is called. If the guard
+    /// is dropped without committing, the arena's length is unchanged and the space is used by
+    /// the next allocation instead. Anything already written into it is leaked, not dropped.
+    ///
+    /// The arena stays borrowed while the guard is alive. Using the arena in the meantime, for
+    /// example allocating another value, panics.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::ptr;
+    /// use typed_arena::Arena;
+    ///
+    /// let arena: Arena<[u64; 512]> = Arena::new();
+    ///
+    /// let mut guard = arena.alloc_guard();
+    /// let zeros = unsafe {
+    ///     // Zero the value in place, without building it on the stack first.
+    ///     ptr::write_bytes(guard.slot().as_mut_ptr(), 0, 1);
+    ///     guard.commit()
+    /// };
+    ///
+    /// assert!(zeros.iter().all(|&x| x == 0));
+    /// assert_eq!(arena.len(), 1);
+    /// ```
+    pub fn alloc_guard(&self) -> AllocGuard<'_, T> {
+        let mut chunks = self.chunks.borrow_mut();
+        if chunks.current.len() == chunks.current.capacity() {
+            chunks.reserve(1);
         }
+        AllocGuard { chunks }
     }
 
     /// Convert this `Arena` into a `Vec<T>`.
@@ -428,23 +695,51 @@ impl<T> Arena<T> {
     /// assert_eq!(easy_as_123, vec!["a", "b", "c"]);
     /// ```
     pub fn into_vec(self) -> Vec<T> {
+        let mut result = Vec::new();
+        self.collect_into(&mut result);
+        result
+    }
+
+    /// Moves all values of this `Arena` to the end of an existing `Vec<T>`.
+    ///
+    /// Like with [`into_vec`][Arena::into_vec], the values are appended in the order that they
+    /// were allocated in. This is useful for gathering the values of several arenas into one
+    /// buffer.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let first = Arena::new();
+    /// first.alloc_extend(0..3);
+    /// let second = Arena::new();
+    /// second.alloc_extend(3..5);
+    ///
+    /// let mut all = Vec::new();
+    /// first.collect_into(&mut all);
+    /// second.collect_into(&mut all);
+    ///
+    /// assert_eq!(all, vec![0, 1, 2, 3, 4]);
+    /// ```
+    pub fn collect_into(self, dst: &mut Vec<T>) {
         let mut chunks = self.chunks.into_inner();
         // keep order of allocation in the resulting Vec
         let n = chunks
             .rest
             .iter()
             .fold(chunks.current.len(), |a, v| a + v.len());
-        let mut result = Vec::with_capacity(n);
+        dst.reserve(n);
         for mut vec in chunks.rest {
-            result.append(&mut vec);
+            dst.append(&mut vec);
         }
-        result.append(&mut chunks.current);
-        result
+        dst.append(&mut chunks.current);
     }
 
     /// Returns an iterator that allows modifying each value.
     ///
-    /// Items are yielded in the order that they were allocated.
+    /// Items are yielded in the order that they were allocated. The iterator is double-ended, so
+    /// `iter_mut().rev()` yields them starting with the most recently allocated one.
     ///
     /// ## Example
     ///
@@ -469,6 +764,26 @@ impl<T> Arena<T> {
     ///
     /// ```
     ///
+    /// A mutable reference to the arena can be iterated directly as well:
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// #[derive(Debug, PartialEq, Eq)]
+    /// struct Point { x: i32, y: i32 };
+    ///
+    /// let mut arena = Arena::new();
+    ///
+    /// arena.alloc(Point { x: 0, y: 0 });
+    /// arena.alloc(Point { x: 1, y: 1 });
+    ///
+    /// for point in &mut arena {
+    ///     point.y += 10;
+    /// }
+    ///
+    /// assert_eq!(arena.into_vec(), vec![Point { x: 0, y: 10 }, Point { x: 1, y: 11 }]);
+    /// ```
+    ///
     /// ## Immutable Iteration
     ///
     /// Note that there is no corresponding `iter` method. Access to the arena's contents
@@ -488,145 +803,1346 @@ impl<T> Arena<T> {
     /// // borrow error!
     /// *x = 2;
     /// ```
+    ///
+    /// Likewise, nothing can be allocated while iterating, since that could make the arena
+    /// start a new chunk under the iterator's feet:
+    ///
+    /// ```compile_fail
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc(1);
+    ///
+    /// for _ in arena.iter_mut() {
+    ///     // borrow error!
+    ///     arena.alloc(2);
+    /// }
+    /// ```
+    ///
+    /// Once you are done allocating, [`as_mut_slice`][Arena::as_mut_slice] gives shared access
+    /// to the values too. Its `iter` is a regular slice iterator, which can be cloned to restart
+    /// iteration.
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(1..4);
+    ///
+    /// let iter = arena.as_mut_slice().iter();
+    /// let sum: i32 = iter.clone().sum();
+    /// let max = iter.max();
+    ///
+    /// assert_eq!(sum, 6);
+    /// assert_eq!(max, Some(&3));
+    /// ```
     #[inline]
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let remaining = self.len();
         let chunks = self.chunks.get_mut();
-        let position = if !chunks.rest.is_empty() {
-            let index = 0;
-            let inner_iter = chunks.rest[index].iter_mut();
-            // Extend the lifetime of the individual elements to that of the arena.
-            // This is OK because we borrow the arena mutably to prevent new allocations
-            // and we take care here to never move items inside the arena while the
-            // iterator is alive.
-            let inner_iter = unsafe { mem::transmute(inner_iter) };
-            IterMutState::ChunkListRest { index, inner_iter }
-        } else {
-            // Extend the lifetime of the individual elements to that of the arena.
-            let iter = unsafe { mem::transmute(chunks.current.iter_mut()) };
-            IterMutState::ChunkListCurrent { iter }
-        };
-        IterMut {
-            chunks,
-            state: position,
-        }
+        // Each chunk is only borrowed once it is reached from either end, so the iterator never
+        // holds two references to the same value.
+        let inner = chunks
+            .rest
+            .iter_mut()
+            .chain(iter::once(&mut chunks.current))
+            .flatten();
+        IterMut { inner, remaining }
     }
-}
 
-impl Arena<u8> {
-    /// Allocates a string slice and returns a mutable reference to it.
+    /// Returns a mutable slice of all the values in the arena.
     ///
-    /// This is on `Arena<u8>`, because string slices use byte slices (`[u8]`) as their backing
-    /// storage.
+    /// Items in the slice appear in the order that they were allocated in.
     ///
-    /// # Example
+    /// The values may be spread over several chunks of memory. If they are, they are first moved
+    /// into a single chunk, so that they can be viewed as one slice. Further calls are cheap until
+    /// the arena needs to allocate a new chunk again. Moving the values is fine here, because the
+    /// arena is borrowed mutably, so there can't be any references to them. The unused space moves
+    /// along with them, which invalidates pointers returned by
+    /// [`uninitialized_array`][Arena::uninitialized_array].
+    ///
+    /// ## Example
     ///
     /// ```
     /// use typed_arena::Arena;
     ///
-    /// let arena: Arena<u8> = Arena::new();
-    /// let hello = arena.alloc_str("Hello world");
-    /// assert_eq!("Hello world", hello);
+    /// let mut arena = Arena::with_capacity(2);
+    /// for s in &["a", "b", "c"] {
+    ///     arena.alloc(s.to_string());
+    /// }
+    ///
+    /// for s in arena.as_mut_slice() {
+    ///     s.push('!');
+    /// }
+    ///
+    /// assert_eq!(arena.as_mut_slice(), ["a!", "b!", "c!"]);
     /// ```
-    #[inline]
-    pub fn alloc_str(&self, s: &str) -> &mut str {
-        let buffer = self.alloc_extend(s.bytes());
-        // Can't fail the utf8 validation, it already came in as utf8
-        unsafe { str::from_utf8_unchecked_mut(buffer) }
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        let chunks = self.chunks.get_mut();
+        if !chunks.rest.is_empty() {
+            chunks.coalesce();
+        }
+        &mut chunks.current[..]
     }
-}
 
-impl<T> Default for Arena<T> {
-    fn default() -> Self {
-        Self::new()
+    /// Returns a slice of cells over all the values in the arena.
+    ///
+    /// This allows mutating several values through shared references at once, for example to
+    /// update one node of a graph based on its neighbours. Like
+    /// [`as_mut_slice`][Arena::as_mut_slice], this may first move the values into a single chunk.
+    ///
+    /// This takes `&mut self` so that the cells can't alias values that are still borrowed from
+    /// earlier allocations; see [Reading values](struct.Arena.html#reading-values).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(vec![1, 2, 3]);
+    ///
+    /// let cells = arena.as_cell_slice();
+    /// cells[0].swap(&cells[2]);
+    /// cells[1].set(cells[0].get() + cells[2].get());
+    ///
+    /// assert_eq!(arena.into_vec(), vec![3, 4, 1]);
+    /// ```
+    pub fn as_cell_slice(&mut self) -> &[Cell<T>] {
+        Cell::from_mut(self.as_mut_slice()).as_slice_of_cells()
     }
-}
 
-impl<T> ChunkList<T> {
-    #[inline(never)]
-    #[cold]
-    fn reserve(&mut self, additional: usize) {
-        let double_cap = self
-            .current
-            .capacity()
-            .checked_mul(2)
-            .expect("capacity overflow");
-        let required_cap = additional
-            .checked_next_power_of_two()
-            .expect("capacity overflow");
-        let new_capacity = cmp::max(double_cap, required_cap);
-        let chunk = mem::replace(&mut self.current, Vec::with_capacity(new_capacity));
-        self.rest.push(chunk);
+    /// Returns a value whose `Debug` output lists all values of the arena, next to the summary
+    /// that the arena's own `Debug` implementation shows.
+    ///
+    /// Unlike formatting [`as_mut_slice`][Arena::as_mut_slice], this never moves the values. It
+    /// takes `&mut self` because the values can't be read through `&self`; see [Reading values](struct.Arena.html#reading-values).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::with_capacity(4);
+    /// arena.alloc_extend(vec!["a", "b", "c"]);
+    ///
+    /// assert_eq!(
+    ///     format!("{:?}", arena.debug_full()),
+    ///     r#"Arena { len: 3, capacity: 4, values: ["a", "b", "c"] }"#
+    /// );
+    /// ```
+    pub fn debug_full(&mut self) -> impl fmt::Debug + '_
+    where
+        T: fmt::Debug,
+    {
+        DebugFull {
+            chunks: self.chunks.get_mut(),
+        }
     }
-}
-
-enum IterMutState<'a, T> {
-    ChunkListRest {
-        index: usize,
-        inner_iter: slice::IterMut<'a, T>,
-    },
-    ChunkListCurrent {
-        iter: slice::IterMut<'a, T>,
-    },
-}
 
-/// Mutable arena iterator.
-///
-/// This struct is created by the [`iter_mut`](struct.Arena.html#method.iter_mut) method on [Arenas](struct.Arena.html).
-pub struct IterMut<'a, T: 'a> {
-    chunks: &'a mut ChunkList<T>,
-    state: IterMutState<'a, T>,
-}
+    /// Returns a mutable slice of the values in the given range of allocation indices, or `None`
+    /// if the range is out of bounds.
+    ///
+    /// If the range lies within a single chunk of memory, this returns a slice of that chunk.
+    /// Otherwise the values are first moved into a single chunk, like
+    /// [`as_mut_slice`][Arena::as_mut_slice] does, which takes time proportional to the length of
+    /// the whole arena.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(0..5);
+    ///
+    /// assert_eq!(arena.get_range_mut(1..4), Some(&mut [1, 2, 3][..]));
+    /// assert_eq!(arena.get_range_mut(3..6), None);
+    /// ```
+    pub fn get_range_mut(&mut self, range: Range<usize>) -> Option<&mut [T]> {
+        if range.start > range.end || range.end > self.len() {
+            return None;
+        }
+
+        // Find the chunk that holds the whole range, if there is one.
+        let chunks = self.chunks.get_mut();
+        let mut found = None;
+        let mut start = 0;
+        for (i, vec) in chunks
+            .rest
+            .iter()
+            .chain(iter::once(&chunks.current))
+            .enumerate()
+        {
+            let end = start + vec.len();
+            if range.end <= end {
+                if range.start >= start {
+                    found = Some((i, start));
+                }
+                break;
+            }
+            start = end;
+        }
+
+        match found {
+            Some((i, start)) => {
+                let vec = if i < chunks.rest.len() {
+                    &mut chunks.rest[i]
+                } else {
+                    &mut chunks.current
+                };
+                Some(&mut vec[range.start - start..range.end - start])
+            }
+            None => {
+                chunks.coalesce();
+                Some(&mut chunks.current[range])
+            }
+        }
+    }
+
+    /// Returns a mutable reference to the value with the given allocation index, or `None` if
+    /// the index is out of bounds.
+    ///
+    /// Unlike [`get_range_mut`][Arena::get_range_mut], this never moves the values.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(vec!['a', 'b', 'c']);
+    ///
+    /// assert_eq!(arena.get_mut(1), Some(&mut 'b'));
+    /// assert_eq!(arena.get_mut(3), None);
+    /// ```
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let chunks = self.chunks.get_mut();
+        let mut index = index;
+        for vec in chunks
+            .rest
+            .iter_mut()
+            .chain(iter::once(&mut chunks.current))
+        {
+            let len = vec.len();
+            if index < len {
+                return vec.get_mut(index);
+            }
+            index -= len;
+        }
+        None
+    }
+
+    /// Returns a mutable reference to a value, counting back from the most recently allocated
+    /// one, or `None` if there are not that many values.
+    ///
+    /// `get_back(0)` is the last value, `get_back(1)` the one before it, and so on.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(vec!['a', 'b', 'c']);
+    ///
+    /// assert_eq!(arena.get_back(0), Some(&mut 'c'));
+    /// assert_eq!(arena.get_back(2), Some(&mut 'a'));
+    /// assert_eq!(arena.get_back(3), None);
+    /// ```
+    pub fn get_back(&mut self, from_end: usize) -> Option<&mut T> {
+        let chunks = self.chunks.get_mut();
+        let mut from_end = from_end;
+        for vec in iter::once(&mut chunks.current).chain(chunks.rest.iter_mut().rev()) {
+            let len = vec.len();
+            if from_end < len {
+                return vec.get_mut(len - 1 - from_end);
+            }
+            from_end -= len;
+        }
+        None
+    }
+
+    /// Returns `true` if the arena holds the same values as the given iterator, in allocation
+    /// order.
+    ///
+    /// The comparison stops at the first value that differs, or as soon as one side runs out of
+    /// values before the other.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(0..5);
+    ///
+    /// assert!(arena.eq_iter(0..5));
+    /// assert!(!arena.eq_iter(0..4));
+    /// assert!(!arena.eq_iter(vec![0, 1, 2, 3, 5]));
+    /// ```
+    pub fn eq_iter<I>(&mut self, iter: I) -> bool
+    where
+        T: PartialEq,
+        I: IntoIterator<Item = T>,
+    {
+        let mut iter = iter.into_iter();
+        for value in self.iter_mut() {
+            match iter.next() {
+                Some(ref other) if *value == *other => {}
+                _ => return false,
+            }
+        }
+        iter.next().is_none()
+    }
+
+    /// Returns a mutable reference to the value that gives the maximum value from the given
+    /// function, or `None` if the arena is empty.
+    ///
+    /// Like `Iterator::max_by_key`, if several values are equally maximum, the last one is
+    /// returned.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(vec!["a", "ccc", "bb"]);
+    ///
+    /// assert_eq!(arena.max_by_key(|s| s.len()), Some(&mut "ccc"));
+    /// ```
+    pub fn max_by_key<K, F>(&mut self, mut f: F) -> Option<&mut T>
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.iter_mut().max_by_key(|value| f(value))
+    }
+
+    /// Returns a mutable reference to the value that gives the minimum value from the given
+    /// function, or `None` if the arena is empty.
+    ///
+    /// Like `Iterator::min_by_key`, if several values are equally minimum, the first one is
+    /// returned.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(vec!["a", "ccc", "bb"]);
+    ///
+    /// assert_eq!(arena.min_by_key(|s| s.len()), Some(&mut "a"));
+    /// ```
+    pub fn min_by_key<K, F>(&mut self, mut f: F) -> Option<&mut T>
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.iter_mut().min_by_key(|value| f(value))
+    }
+
+    /// Clones the values in the given range of allocation indices and allocates the clones at
+    /// the end of the arena. Returns a mutable slice that contains the clones.
+    ///
+    /// The range is looked up like with [`get_range_mut`][Arena::get_range_mut], so the values
+    /// are only moved into a single chunk if the range spans several chunks.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the range is out of bounds.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(vec!['a', 'b', 'c', 'd']);
+    ///
+    /// assert_eq!(arena.extend_from_within(0..2), ['a', 'b']);
+    /// assert_eq!(arena.into_vec(), vec!['a', 'b', 'c', 'd', 'a', 'b']);
+    /// ```
+    pub fn extend_from_within(&mut self, range: Range<usize>) -> &mut [T]
+    where
+        T: Clone,
+    {
+        // Clone into a temporary first, since the clones may end up in the same chunk that
+        // they are cloned from.
+        let values = match self.get_range_mut(range.clone()) {
+            Some(values) => values.to_vec(),
+            None => panic!(
+                "range {:?} out of bounds for an arena of length {}",
+                range,
+                self.len()
+            ),
+        };
+        self.alloc_extend(values)
+    }
+
+    /// Returns a new arena holding clones of all values in this one, in the same order.
+    ///
+    /// The two arenas are independent afterwards. This takes `&mut self` rather than being a
+    /// `Clone` implementation, because the values can't be read through `&self`; see
+    /// [Reading values](struct.Arena.html#reading-values).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc(String::from("a"));
+    ///
+    /// let snapshot = arena.clone_arena();
+    /// arena.alloc(String::from("b"));
+    /// snapshot.alloc(String::from("c"));
+    ///
+    /// assert_eq!(arena.into_vec(), ["a", "b"]);
+    /// assert_eq!(snapshot.into_vec(), ["a", "c"]);
+    /// ```
+    pub fn clone_arena(&mut self) -> Arena<T>
+    where
+        T: Clone,
+    {
+        let arena = Arena::with_capacity(self.len());
+        arena.alloc_extend(self.iter_mut().map(|value| value.clone()));
+        arena
+    }
+
+    /// Shortens the arena, keeping the first `new_len` values and dropping the rest.
+    ///
+    /// If `new_len` is greater than or equal to the arena's current length, this has no effect.
+    /// The removed values are dropped front to back, in the order that they were allocated. See
+    /// [`truncate_rev`][Arena::truncate_rev] to drop them the other way around. Space freed in the
+    /// current chunk is reused by later allocations, but space freed in earlier chunks is not.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(0..5);
+    ///
+    /// arena.truncate(2);
+    ///
+    /// assert_eq!(arena.into_vec(), vec![0, 1]);
+    /// ```
+    pub fn truncate(&mut self, new_len: usize) {
+        let chunks = self.chunks.get_mut();
+        let mut keep = new_len;
+        for vec in chunks
+            .rest
+            .iter_mut()
+            .chain(iter::once(&mut chunks.current))
+        {
+            if keep >= vec.len() {
+                keep -= vec.len();
+            } else {
+                vec.truncate(keep);
+                keep = 0;
+            }
+        }
+    }
+
+    /// Shortens the arena like [`truncate`][Arena::truncate], but drops the removed values back
+    /// to front, starting with the one that was allocated last.
+    ///
+    /// This is useful when later values may refer to earlier ones while they are dropped.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use typed_arena::Arena;
+    ///
+    /// struct Noisy<'a>(u32, &'a RefCell<Vec<u32>>);
+    ///
+    /// impl<'a> Drop for Noisy<'a> {
+    ///     fn drop(&mut self) {
+    ///         self.1.borrow_mut().push(self.0);
+    ///     }
+    /// }
+    ///
+    /// let dropped = RefCell::new(Vec::new());
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend((0..4).map(|i| Noisy(i, &dropped)));
+    ///
+    /// arena.truncate_rev(1);
+    ///
+    /// assert_eq!(*dropped.borrow(), vec![3, 2, 1]);
+    /// assert_eq!(arena.len(), 1);
+    /// ```
+    pub fn truncate_rev(&mut self, new_len: usize) {
+        let mut len = self.len();
+        let chunks = self.chunks.get_mut();
+        for vec in iter::once(&mut chunks.current).chain(chunks.rest.iter_mut().rev()) {
+            while len > new_len {
+                match vec.pop() {
+                    Some(value) => {
+                        len -= 1;
+                        mem::drop(value);
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Drops all values in the arena, keeping the memory of its current chunk for later
+    /// allocations.
+    ///
+    /// Values are dropped in the order that they were allocated. The earlier, smaller chunks are
+    /// freed, so the arena is left with a single chunk of the largest capacity it has grown to.
+    /// [`Pool`] wraps this pattern of reusing an arena.
+    ///
+    /// ## Example
+    ///
+    /// Reusing one arena for a series of frames, instead of creating a new arena for each of them:
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// for frame in 0..3 {
+    ///     for i in 0..100 {
+    ///         arena.alloc(frame * 100 + i);
+    ///     }
+    ///     assert_eq!(arena.len(), 100);
+    ///
+    ///     // Done with this frame: drop its values, but keep the memory.
+    ///     arena.clear();
+    ///     assert!(arena.is_empty());
+    /// }
+    /// ```
+    pub fn clear(&mut self) {
+        let chunks = self.chunks.get_mut();
+        chunks.rest.clear();
+        chunks.current.clear();
+    }
+
+    /// Retains only the values specified by the predicate.
+    ///
+    /// In other words, removes all values `x` for which `f(&x)` returns `false`. The predicate is
+    /// called exactly once for each value, in the order that they were allocated.
+    ///
+    /// Like `Vec::retain`, this is stable: the values that are kept stay in their original order
+    /// of allocation. Space freed in the current chunk is reused by later allocations, but space
+    /// freed in earlier chunks is not.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(0..10);
+    ///
+    /// arena.retain(|&x| x % 2 == 0);
+    ///
+    /// assert_eq!(arena.into_vec(), vec![0, 2, 4, 6, 8]);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.retain_indexed(|_, value| f(value))
+    }
+
+    /// Retains only the values specified by the predicate, which also receives the allocation
+    /// index of each value.
+    ///
+    /// The index is the position that the value had before any values were removed by this
+    /// call. Otherwise this works like [`retain`][Arena::retain].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(vec!['a', 'b', 'c', 'd', 'e', 'f', 'g']);
+    ///
+    /// arena.retain_indexed(|i, _| i % 3 == 0);
+    ///
+    /// assert_eq!(arena.into_vec(), vec!['a', 'd', 'g']);
+    /// ```
+    pub fn retain_indexed<F>(&mut self, mut f: F)
+    where
+        F: FnMut(usize, &T) -> bool,
+    {
+        let chunks = self.chunks.get_mut();
+        let mut index = 0;
+        let mut f = |value: &T| {
+            let keep = f(index, value);
+            index += 1;
+            keep
+        };
+        // Chunks are stored in allocation order, so retaining within each of them in turn keeps
+        // the overall order.
+        for vec in chunks.rest.iter_mut() {
+            vec.retain(&mut f);
+        }
+        chunks.current.retain(&mut f);
+    }
+
+    /// Frees the memory that isn't used by any value.
+    ///
+    /// After [`truncate`][Arena::truncate] or [`retain`][Arena::retain], some chunks may be left
+    /// with few or no values in them. This drops the empty chunks and shrinks the capacity of the
+    /// others to the number of values they hold, which may move the values in memory. The next
+    /// allocation will then start a new chunk.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::with_capacity(4);
+    /// arena.alloc_extend(0..100);
+    ///
+    /// arena.truncate(2);
+    /// arena.shrink_to_fit();
+    ///
+    /// assert_eq!(arena.as_mut_slice(), [0, 1]);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        let chunks = self.chunks.get_mut();
+        chunks.rest.retain(|vec| !vec.is_empty());
+        if chunks.current.is_empty() {
+            if let Some(vec) = chunks.rest.pop() {
+                chunks.current = vec;
+            }
+        }
+        for vec in chunks.rest.iter_mut() {
+            vec.shrink_to_fit();
+        }
+        chunks.rest.shrink_to_fit();
+        chunks.current.shrink_to_fit();
+    }
+
+    /// Moves all values out of the arena, passing them to `sink` in the order that they were
+    /// allocated.
+    ///
+    /// Afterwards the arena is empty, and the memory of its current chunk is kept for later
+    /// allocations. If `sink` panics, the values that it hasn't received yet are dropped, and the
+    /// arena is left empty as well.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc_extend(vec![String::from("a"), String::from("b")]);
+    ///
+    /// let mut joined = String::new();
+    /// arena.drain_into(|s| joined.push_str(&s));
+    ///
+    /// assert_eq!(joined, "ab");
+    /// assert_eq!(arena.len(), 0);
+    /// ```
+    pub fn drain_into<F>(&mut self, mut sink: F)
+    where
+        F: FnMut(T),
+    {
+        let chunks = self.chunks.get_mut();
+        // Start draining the current chunk up front, so that it's emptied even if `sink` panics
+        // while the earlier chunks are being drained.
+        let current = chunks.current.drain(..);
+        for vec in chunks.rest.drain(..) {
+            for value in vec {
+                sink(value);
+            }
+        }
+        for value in current {
+            sink(value);
+        }
+    }
+}
+
+impl Arena<u8> {
+    /// Allocates a copy of a byte slice and returns a mutable reference to it.
+    ///
+    /// The bytes are copied all at once, like with
+    /// [`alloc_slice_copy`][Arena::alloc_slice_copy].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let arena: Arena<u8> = Arena::new();
+    /// let bytes = arena.alloc_bytes(&[0xde, 0xad, 0xbe, 0xef]);
+    /// assert_eq!(bytes, [0xde, 0xad, 0xbe, 0xef]);
+    /// ```
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_bytes(&self, bytes: &[u8]) -> &mut [u8] {
+        self.alloc_slice_copy(bytes)
+    }
+
+    /// Allocates a string slice and returns a mutable reference to it.
+    ///
+    /// This is on `Arena<u8>`, because string slices use byte slices (`[u8]`) as their backing
+    /// storage.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let arena: Arena<u8> = Arena::new();
+    /// let hello = arena.alloc_str("Hello world");
+    /// assert_eq!("Hello world", hello);
+    /// ```
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_str(&self, s: &str) -> &mut str {
+        let buffer = self.alloc_bytes(s.as_bytes());
+        // Can't fail the utf8 validation, it already came in as utf8
+        unsafe { str::from_utf8_unchecked_mut(buffer) }
+    }
+
+    /// Allocates a string slice like [`alloc_str`][Arena::alloc_str], and also returns the range
+    /// of allocation indices that its bytes occupy.
+    ///
+    /// The range stays valid as long as no values are removed from the arena, so it can be stored
+    /// instead of the string itself and resolved later, for example through
+    /// [`as_mut_slice`][Arena::as_mut_slice].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::str;
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena: Arena<u8> = Arena::new();
+    /// let (hello, _) = arena.alloc_str_span("Hello");
+    /// let (world, _) = arena.alloc_str_span("world");
+    /// assert_eq!(hello, 0..5);
+    /// assert_eq!(world, 5..10);
+    ///
+    /// let bytes = arena.as_mut_slice();
+    /// assert_eq!(str::from_utf8(&bytes[world]), Ok("world"));
+    /// ```
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_str_span(&self, s: &str) -> (Range<usize>, &mut str) {
+        let start = self.len();
+        (start..start + s.len(), self.alloc_str(s))
+    }
+
+    /// Formats the arguments directly into the arena, and returns a mutable reference to the
+    /// resulting string.
+    ///
+    /// Unlike `arena.alloc_str(&format!(...))`, this doesn't build an intermediate `String`, so
+    /// it also works without the standard library. The arena can't be used while the arguments
+    /// are being formatted: a `Display` implementation that allocates in the same arena panics.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a formatting trait implementation returns an error, like `format!` does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let arena: Arena<u8> = Arena::new();
+    /// let greeting = arena.alloc_fmt(format_args!("Hello {}!", 42));
+    /// assert_eq!(greeting, "Hello 42!");
+    /// ```
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_fmt(&self, args: fmt::Arguments) -> &mut str {
+        let mut writer = self.str_writer();
+        if fmt::write(&mut writer, args).is_err() {
+            let start = writer.start;
+            writer.chunks.current.truncate(start);
+            panic!("a formatting trait implementation returned an error");
+        }
+        writer.finish()
+    }
+
+    /// Returns a writer that appends text to the arena, for building a string from several
+    /// `write!` calls.
+    ///
+    /// Everything written ends up in one contiguous string, which
+    /// [`finish`][StrWriter::finish] returns. The arena is locked until then, like with
+    /// [`alloc_guard`][Arena::alloc_guard]: allocating through it in the meantime panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fmt::Write;
+    /// use typed_arena::Arena;
+    ///
+    /// let arena: Arena<u8> = Arena::new();
+    /// let mut writer = arena.str_writer();
+    /// for i in 0..3 {
+    ///     write!(writer, "{} ", i).unwrap();
+    /// }
+    /// let line = writer.finish();
+    ///
+    /// assert_eq!(line, "0 1 2 ");
+    /// ```
+    pub fn str_writer(&self) -> StrWriter<'_> {
+        let chunks = self.chunks.borrow_mut();
+        let start = chunks.current.len();
+        StrWriter { chunks, start }
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Allocates every value of the iterator into a new arena.
+///
+/// ## Example
+///
+/// ```
+/// use typed_arena::Arena;
+///
+/// let arena: Arena<_> = (0..3).map(|i| i * 2).collect();
+/// assert_eq!(arena.into_vec(), vec![0, 2, 4]);
+/// ```
+impl<T> FromIterator<T> for Arena<T> {
+    fn from_iter<I>(iterable: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        // Make room for all of the values up front, rather than leaving a default-sized first
+        // chunk unused when `alloc_extend` moves on to a chunk that fits them.
+        let iter = iterable.into_iter();
+        let default_capacity = INITIAL_SIZE / cmp::max(1, mem::size_of::<T>());
+        let arena = Arena::with_capacity(cmp::max(iter.size_hint().0, default_capacity));
+        arena.alloc_extend(iter);
+        arena
+    }
+}
+
+/// Collects the values of a parallel iterator into a new arena, in the order of the source.
+///
+/// The values are collected into a `Vec` in parallel, which then becomes the arena's only chunk.
+/// Requires the `rayon` feature.
+///
+/// ## Example
+///
+/// ```
+/// # #[cfg(feature = "rayon")]
+/// extern crate rayon;
+/// extern crate typed_arena;
+///
+/// # #[cfg(feature = "rayon")]
+/// # fn main() {
+/// use rayon::prelude::*;
+/// use typed_arena::Arena;
+///
+/// let arena: Arena<_> = (0..100).into_par_iter().map(|i| i * 2).collect();
+/// assert_eq!(arena.len(), 100);
+/// # }
+/// # #[cfg(not(feature = "rayon"))]
+/// # fn main() {}
+/// ```
+#[cfg(feature = "rayon")]
+impl<T: Send> rayon::iter::FromParallelIterator<T> for Arena<T> {
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where
+        I: rayon::iter::IntoParallelIterator<Item = T>,
+    {
+        let values: Vec<T> = rayon::iter::FromParallelIterator::from_par_iter(par_iter);
+        Arena {
+            chunks: RefCell::new(ChunkList {
+                current: values,
+                rest: Vec::new(),
+            }),
+        }
+    }
+}
+
+/// Allocates the values of the iterator through a shared reference, like
+/// [`alloc_extend`][Arena::alloc_extend].
+///
+/// This lets `&Arena` be passed to generic code that expects an `Extend` implementation. Since
+/// the arena grows as needed, every value is allocated.
+///
+/// ## Example
+///
+/// ```
+/// use typed_arena::Arena;
+///
+/// fn add_defaults<E: Extend<u32>>(mut target: E) {
+///     target.extend(vec![1, 2, 3]);
+/// }
+///
+/// let arena = Arena::new();
+/// add_defaults(&arena);
+/// assert_eq!(arena.into_vec(), vec![1, 2, 3]);
+/// ```
+impl<T> Extend<T> for &Arena<T> {
+    fn extend<I>(&mut self, iterable: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        self.alloc_extend(iterable);
+    }
+}
+
+/// Appends the written bytes to the arena, so that `&Arena<u8>` can be passed where an
+/// `io::Write` is expected. Requires the `std` feature.
+///
+/// Every write succeeds and writes the whole buffer, which is copied in bulk like with
+/// [`alloc_bytes`][Arena::alloc_bytes]. Separate writes are not guaranteed to end up next to each
+/// other in memory; see [`str_writer`][Arena::str_writer] for text that should stay contiguous.
+///
+/// ## Example
+///
+/// ```
+/// use std::io::Write;
+/// use typed_arena::Arena;
+///
+/// let arena: Arena<u8> = Arena::new();
+/// write!(&arena, "{}-{}", 1, 2).unwrap();
+/// (&arena).write_all(b"-3").unwrap();
+///
+/// assert_eq!(arena.into_vec(), b"1-2-3");
+/// ```
+#[cfg(feature = "std")]
+impl std::io::Write for &Arena<u8> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.alloc_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Views all values of the arena as one slice, like [`as_mut_slice`][Arena::as_mut_slice].
+///
+/// There is no `AsRef` counterpart, since the values can't be borrowed through `&self`; see
+/// [Reading values](struct.Arena.html#reading-values).
+///
+/// ## Example
+///
+/// ```
+/// use typed_arena::Arena;
+///
+/// fn zero<B: AsMut<[u8]>>(mut buffer: B) {
+///     for byte in buffer.as_mut() {
+///         *byte = 0;
+///     }
+/// }
+///
+/// let mut arena = Arena::new();
+/// arena.alloc_extend(1..4);
+/// zero(&mut arena);
+///
+/// assert_eq!(arena.into_vec(), vec![0, 0, 0]);
+/// ```
+impl<T> AsMut<[T]> for Arena<T> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+/// Shows a summary of the arena, not its values, so that large arenas don't end up in logs by
+/// accident. This also means `T` doesn't need to implement `Debug`.
+///
+/// The values couldn't be shown anyway, since `Debug::fmt` only gets `&self`; see
+/// [Reading values](struct.Arena.html#reading-values). To print all values, use [`debug_full`][Arena::debug_full] instead.
+///
+/// While the arena is locked by an [`AllocGuard`] or a [`StrWriter`], this prints
+/// `Arena { <locked> }` instead of panicking.
+///
+/// ## Example
+///
+/// ```
+/// use typed_arena::Arena;
+///
+/// let mut arena = Arena::with_capacity(4);
+/// arena.alloc_extend(vec!["a", "b", "c"]);
+///
+/// println!("{:?}", arena);
+/// println!("{:?}", arena.debug_full());
+///
+/// assert_eq!(format!("{:?}", arena), "Arena { len: 3, capacity: 4 }");
+/// ```
+impl<T> fmt::Debug for Arena<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let chunks = match self.chunks.try_borrow() {
+            Ok(chunks) => chunks,
+            Err(_) => return f.write_str("Arena { <locked> }"),
+        };
+        let len = chunks
+            .rest
+            .iter()
+            .fold(chunks.current.len(), |a, v| a + v.len());
+        let capacity = len + (chunks.current.capacity() - chunks.current.len());
+        f.debug_struct("Arena")
+            .field("len", &len)
+            .field("capacity", &capacity)
+            .finish()
+    }
+}
+
+struct DebugFull<'a, T: 'a> {
+    chunks: &'a ChunkList<T>,
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for DebugFull<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let chunks = self.chunks;
+        let len = chunks
+            .rest
+            .iter()
+            .fold(chunks.current.len(), |a, v| a + v.len());
+        let capacity = len + (chunks.current.capacity() - chunks.current.len());
+        f.debug_struct("Arena")
+            .field("len", &len)
+            .field("capacity", &capacity)
+            .field("values", chunks)
+            .finish()
+    }
+}
+
+/// Moves the values out of the arena, in the order that they were allocated.
+///
+/// ## Example
+///
+/// ```
+/// use typed_arena::Arena;
+///
+/// let arena = Arena::new();
+/// arena.alloc_extend(vec![String::from("a"), String::from("b")]);
+///
+/// let mut joined = String::new();
+/// for s in arena {
+///     joined += &s;
+/// }
+/// assert_eq!(joined, "ab");
+/// ```
+impl<T> IntoIterator for Arena<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        let remaining = self.len();
+        let mut chunks = self.chunks.into_inner();
+        chunks.rest.push(chunks.current);
+        let inner = chunks.rest.into_iter().flatten();
+        IntoIter { inner, remaining }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Arena<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ChunkList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.rest.iter().flatten().chain(&self.current))
+            .finish()
+    }
+}
+
+impl<T> ChunkList<T> {
+    #[inline(never)]
+    #[cold]
+    fn reserve(&mut self, additional: usize) {
+        let double_cap = self
+            .current
+            .capacity()
+            .checked_mul(2)
+            .expect("capacity overflow");
+        let required_cap = additional
+            .checked_next_power_of_two()
+            .expect("capacity overflow");
+        let new_capacity = cmp::max(double_cap, required_cap);
+        let chunk = mem::replace(&mut self.current, Vec::with_capacity(new_capacity));
+        self.rest.push(chunk);
+    }
+
+    /// Moves all values into the current chunk, in allocation order, keeping the amount of
+    /// unused capacity it had.
+    fn coalesce(&mut self) {
+        let spare = self.current.capacity() - self.current.len();
+        let len = self
+            .rest
+            .iter()
+            .fold(self.current.len(), |a, v| a + v.len());
+        let mut result = Vec::with_capacity(len + spare);
+        for mut vec in self.rest.drain(..) {
+            result.append(&mut vec);
+        }
+        result.append(&mut self.current);
+        self.current = result;
+    }
+}
+
+/// A reusable arena, for allocating a series of short-lived batches of values in the same
+/// memory.
+///
+/// Each batch [acquires](struct.Pool.html#method.acquire) the pool's arena. When the returned
+/// guard is dropped, the arena is [cleared](struct.Arena.html#method.clear): its values are
+/// dropped, but its memory is kept for the next batch.
+///
+/// ## Example
+///
+/// ```
+/// use typed_arena::Pool;
+///
+/// let mut pool = Pool::new();
+/// for frame in 0..3 {
+///     let arena = pool.acquire();
+///     for i in 0..100 {
+///         arena.alloc(frame * 100 + i);
+///     }
+///     assert_eq!(arena.len(), 100);
+/// }
+/// ```
+pub struct Pool<T> {
+    arena: Arena<T>,
+}
+
+impl<T> Pool<T> {
+    /// Construct a new pool.
+    pub fn new() -> Pool<T> {
+        Pool {
+            arena: Arena::new(),
+        }
+    }
+
+    /// Construct a new pool whose arena has capacity for `n` values pre-allocated.
+    pub fn with_capacity(n: usize) -> Pool<T> {
+        Pool {
+            arena: Arena::with_capacity(n),
+        }
+    }
+
+    /// Returns the pool's arena, which is empty. The arena is cleared again when the guard is
+    /// dropped.
+    pub fn acquire(&mut self) -> PoolGuard<'_, T> {
+        // A guard that was leaked with `mem::forget` didn't clear the arena.
+        self.arena.clear();
+        PoolGuard {
+            arena: &mut self.arena,
+        }
+    }
+}
+
+impl<T> Default for Pool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An arena acquired from a [`Pool`], which is cleared when this guard is dropped.
+///
+/// This struct is created by the [`acquire`](struct.Pool.html#method.acquire) method on [Pools](struct.Pool.html).
+pub struct PoolGuard<'a, T: 'a> {
+    arena: &'a mut Arena<T>,
+}
+
+impl<'a, T> Deref for PoolGuard<'a, T> {
+    type Target = Arena<T>;
+
+    fn deref(&self) -> &Arena<T> {
+        self.arena
+    }
+}
+
+impl<'a, T> DerefMut for PoolGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut Arena<T> {
+        self.arena
+    }
+}
+
+impl<'a, T> Drop for PoolGuard<'a, T> {
+    fn drop(&mut self) {
+        self.arena.clear();
+    }
+}
+
+/// A writer that appends text to an `Arena<u8>` as one contiguous string.
+///
+/// This struct is created by the [`str_writer`](struct.Arena.html#method.str_writer) method on [Arenas](struct.Arena.html).
+/// Text written to it stays in the arena even if the writer is dropped without calling
+/// [`finish`][StrWriter::finish].
+pub struct StrWriter<'a> {
+    chunks: RefMut<'a, ChunkList<u8>>,
+    /// Index of the first written byte in the current chunk.
+    start: usize,
+}
+
+impl<'a> StrWriter<'a> {
+    /// Unlocks the arena and returns a mutable reference to everything that was written.
+    pub fn finish(mut self) -> &'a mut str {
+        let len = self.chunks.current.len() - self.start;
+        // Extend the lifetime to that of the arena, like `alloc_extend` does. The written bytes
+        // came in as `&str`s, so they are valid UTF-8.
+        unsafe {
+            let start = self.chunks.current.as_mut_ptr().add(self.start);
+            str::from_utf8_unchecked_mut(slice::from_raw_parts_mut(start, len))
+        }
+    }
+}
+
+impl<'a> fmt::Write for StrWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let chunks = &mut *self.chunks;
+        if s.len() > chunks.current.capacity() - chunks.current.len() {
+            // Move what was written so far into a new chunk that also fits `s`, so that the
+            // result stays contiguous.
+            let written = chunks.current.len() - self.start;
+            chunks.reserve(written + s.len());
+            let previous_chunk = chunks.rest.last_mut().unwrap();
+            let previous_chunk_len = previous_chunk.len();
+            chunks
+                .current
+                .extend(previous_chunk.drain(previous_chunk_len - written..));
+            self.start = 0;
+        }
+        chunks.current.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// A reserved slot for constructing a value in place.
+///
+/// This struct is created by the [`alloc_guard`](struct.Arena.html#method.alloc_guard) method on [Arenas](struct.Arena.html).
+pub struct AllocGuard<'a, T: 'a> {
+    chunks: RefMut<'a, ChunkList<T>>,
+}
+
+impl<'a, T> AllocGuard<'a, T> {
+    /// Returns the reserved, uninitialized space for the value.
+    pub fn slot(&mut self) -> &mut MaybeUninit<T> {
+        let len = self.chunks.current.len();
+        debug_assert!(len < self.chunks.current.capacity());
+        // Avoid going through `Vec::deref_mut`, which overlaps
+        // other references we have already handed out!
+        unsafe { &mut *(self.chunks.current.as_mut_ptr().add(len) as *mut MaybeUninit<T>) }
+    }
+
+    /// Confirms the allocation of the value, and returns a mutable reference to it.
+    ///
+    /// ## Safety
+    ///
+    /// The value in [`slot`](#method.slot) must be fully initialized. Once committed, the arena
+    /// considers it allocated and will drop it.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn commit(self) -> &'a mut T {
+        let mut chunks = self.chunks;
+        let len = chunks.current.len();
+        chunks.current.set_len(len + 1);
+        // Extend the lifetime from that of the guard to that of the arena, like
+        // `Arena::alloc` does. The value is never moved while the arena is shared.
+        &mut *chunks.current.as_mut_ptr().add(len)
+    }
+}
+
+// All chunks of a `ChunkList`, in allocation order.
+type ChunksIterMut<'a, T> = iter::Chain<slice::IterMut<'a, Vec<T>>, iter::Once<&'a mut Vec<T>>>;
+
+/// Mutable arena iterator.
+///
+/// This struct is created by the [`iter_mut`](struct.Arena.html#method.iter_mut) method on [Arenas](struct.Arena.html).
+pub struct IterMut<'a, T: 'a> {
+    inner: iter::Flatten<ChunksIterMut<'a, T>>,
+    remaining: usize,
+}
 
 impl<'a, T> Iterator for IterMut<'a, T> {
     type Item = &'a mut T;
+
+    #[inline]
     fn next(&mut self) -> Option<&'a mut T> {
-        loop {
-            self.state = match self.state {
-                IterMutState::ChunkListRest {
-                    mut index,
-                    ref mut inner_iter,
-                } => {
-                    match inner_iter.next() {
-                        Some(item) => return Some(item),
-                        None => {
-                            index += 1;
-                            if index < self.chunks.rest.len() {
-                                let inner_iter = self.chunks.rest[index].iter_mut();
-                                // Extend the lifetime of the individual elements to that of the arena.
-                                let inner_iter = unsafe { mem::transmute(inner_iter) };
-                                IterMutState::ChunkListRest { index, inner_iter }
-                            } else {
-                                let iter = self.chunks.current.iter_mut();
-                                // Extend the lifetime of the individual elements to that of the arena.
-                                let iter = unsafe { mem::transmute(iter) };
-                                IterMutState::ChunkListCurrent { iter }
-                            }
-                        }
-                    }
-                }
-                IterMutState::ChunkListCurrent { ref mut iter } => return iter.next(),
-            };
+        let item = self.inner.next();
+        if item.is_some() {
+            self.remaining -= 1;
         }
+        item
     }
 
+    #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let current_len = self.chunks.current.len();
-        let current_cap = self.chunks.current.capacity();
-        if self.chunks.rest.is_empty() {
-            (current_len, Some(current_len))
-        } else {
-            let rest_len = self.chunks.rest.len();
-            let last_chunk_len = self
-                .chunks
-                .rest
-                .last()
-                .map(|chunk| chunk.len())
-                .unwrap_or(0);
+        (self.remaining, Some(self.remaining))
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.remaining
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        let item = self.inner.next_back();
+        if item.is_some() {
+            self.remaining -= 1;
+        }
+        item
+    }
+}
+
+/// Owning arena iterator.
+///
+/// This struct is created by the `into_iter` method on [Arenas](struct.Arena.html). Values that
+/// are not consumed are dropped along with the iterator.
+pub struct IntoIter<T> {
+    inner: iter::Flatten<vec::IntoIter<Vec<T>>>,
+    remaining: usize,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        let item = self.inner.next();
+        if item.is_some() {
+            self.remaining -= 1;
+        }
+        item
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.remaining
+    }
+}
 
-            let min = current_len + last_chunk_len;
-            let max = min + (rest_len * current_cap / rest_len);
+impl<T> ExactSizeIterator for IntoIter<T> {}
 
-            (min, Some(max))
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        let item = self.inner.next_back();
+        if item.is_some() {
+            self.remaining -= 1;
         }
+        item
     }
 }
@@ -1,5 +1,5 @@
 use super::*;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::mem;
 use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
@@ -11,6 +11,7 @@ impl<'a> Drop for DropTracker<'a> {
     }
 }
 
+#[allow(dead_code)]
 struct Node<'a, 'b: 'a>(Option<&'a Node<'a, 'b>>, u32, DropTracker<'b>);
 
 #[test]
@@ -39,6 +40,7 @@ fn arena_as_intended() {
 
         assert_eq!(arena.len(), 4);
 
+        #[allow(dropping_references)]
         mem::drop(node);
         assert_eq!(drop_counter.get(), 0);
 
@@ -108,7 +110,7 @@ fn test_alloc_uninitialized() {
             assert_eq!(drop_counter.get(), 0);
         }
     }
-    assert_eq!(drop_counter.get(), (0..LIMIT).fold(0, |a, e| a + e) as u32);
+    assert_eq!(drop_counter.get(), (0..LIMIT).sum::<usize>() as u32);
 }
 
 #[test]
@@ -164,7 +166,7 @@ fn alloc_uninitialized_with_panic() {
         let arena: Arena<Dropper> = Arena::new();
         arena.reserve_extend(2);
         let uninitialized = arena.uninitialized_array();
-        assert!((*uninitialized).len() >= 2);
+        assert!((&*uninitialized).len() >= 2);
         ptr::write((*uninitialized)[0].as_mut_ptr(), Dropper(false));
         reached_first_init = true;
         panic!("To drop the arena");
@@ -191,6 +193,7 @@ fn test_uninitialized_array() {
 }
 
 #[test]
+#[allow(clippy::manual_repeat_n)]
 fn dont_trust_the_iterator_size() {
     use std::iter::repeat;
 
@@ -371,3 +374,1413 @@ fn size_hint_many_items() {
         assert_size_hint(i, iter);
     }
 }
+
+#[test]
+fn retain_preserves_order() {
+    let mut arena = Arena::with_capacity(2); // force multiple inner vecs
+    for i in 0..10 {
+        arena.alloc(i);
+    }
+    assert!(
+        arena.chunks.borrow().rest.len() > 1,
+        "expected multiple chunks"
+    );
+
+    arena.retain(|&x| x % 2 == 0);
+    assert_eq!(arena.len(), 5);
+
+    arena.alloc(10);
+    assert_eq!(arena.into_vec(), vec![0, 2, 4, 6, 8, 10]);
+}
+
+#[test]
+fn retain_reuses_space_in_current_chunk() {
+    let mut arena = Arena::with_capacity(4);
+    arena.alloc_extend(0..4);
+
+    arena.retain(|&x| x == 0);
+    assert_eq!(arena.remaining_capacity(), 3);
+    arena.alloc_extend(4..7);
+    assert!(arena.chunks.borrow().rest.is_empty());
+    assert_eq!(arena.into_vec(), vec![0, 4, 5, 6]);
+}
+
+#[test]
+fn as_mut_slice_coalesces_chunks() {
+    let mut arena = Arena::with_capacity(2); // force multiple inner vecs
+    for i in 0..5 {
+        arena.alloc(i.to_string());
+    }
+    assert!(
+        !arena.chunks.borrow().rest.is_empty(),
+        "expected multiple chunks"
+    );
+
+    for s in arena.as_mut_slice() {
+        s.push('!');
+    }
+    assert!(
+        arena.chunks.borrow().rest.is_empty(),
+        "expected a single chunk"
+    );
+
+    let slice = arena.as_mut_slice();
+    assert_eq!(slice, ["0!", "1!", "2!", "3!", "4!"]);
+    slice.swap(0, 4);
+
+    // The arena can still be allocated into after being coalesced.
+    arena.alloc(String::from("5"));
+    assert_eq!(arena.len(), 6);
+    assert_eq!(arena.into_vec(), vec!["4!", "1!", "2!", "3!", "0!", "5"]);
+}
+
+/// Make sure that allocating never invalidates references that were handed out earlier. Miri
+/// checks this when it runs the tests with the stacked borrows model.
+#[test]
+fn allocating_keeps_earlier_references_valid() {
+    let mut arena = Arena::with_capacity(8);
+    {
+        let a = arena.alloc(1);
+        let bc = arena.alloc_extend(vec![2, 3]);
+        let d = arena.alloc(4);
+        // Doesn't fit into the current chunk anymore.
+        let efghi = arena.alloc_extend(vec![5, 6, 7, 8, 9]);
+
+        *a += 10;
+        bc[0] += 10;
+        bc[1] += 10;
+        *d += 10;
+        for x in efghi.iter_mut() {
+            *x += 10;
+        }
+    }
+
+    for x in arena.iter_mut() {
+        *x -= 10;
+    }
+    assert_eq!(arena.into_vec(), (1..10).collect::<Vec<_>>());
+}
+
+/// Every reference yielded by `iter_mut` must stay usable while the iteration goes on, even across
+/// chunk boundaries. Miri checks this when it runs the tests with the stacked borrows model.
+#[test]
+fn iter_mut_references_do_not_overlap() {
+    let mut arena = Arena::with_capacity(2); // force multiple inner vecs
+    for i in 0..10 {
+        arena.alloc(i);
+    }
+    assert!(
+        arena.chunks.borrow().rest.len() > 1,
+        "expected multiple chunks"
+    );
+
+    let refs: Vec<&mut i32> = arena.iter_mut().collect();
+    for x in refs {
+        *x *= 2;
+    }
+
+    assert_eq!(arena.into_vec(), (0..10).map(|x| x * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn as_cell_slice_allows_shared_mutation() {
+    let mut arena = Arena::with_capacity(2); // force multiple inner vecs
+    for i in 0..6 {
+        arena.alloc(i);
+    }
+
+    {
+        let cells = arena.as_cell_slice();
+        assert_eq!(cells.len(), 6);
+        // Every value becomes the sum of itself and its neighbours.
+        let old: Vec<i32> = cells.iter().map(Cell::get).collect();
+        for (i, cell) in cells.iter().enumerate() {
+            let left = if i > 0 { old[i - 1] } else { 0 };
+            let right = old.get(i + 1).cloned().unwrap_or(0);
+            cell.set(left + cell.get() + right);
+        }
+        cells[0].swap(&cells[5]);
+    }
+
+    assert_eq!(arena.into_vec(), vec![9, 3, 6, 9, 12, 1]);
+}
+
+struct OrderTracker<'a>(u32, &'a RefCell<Vec<u32>>);
+impl<'a> Drop for OrderTracker<'a> {
+    fn drop(&mut self) {
+        self.1.borrow_mut().push(self.0);
+    }
+}
+
+#[test]
+fn truncate_drops_front_to_back() {
+    let dropped = RefCell::new(Vec::new());
+    let mut arena = Arena::with_capacity(2); // force multiple inner vecs
+    for i in 0..7 {
+        arena.alloc(OrderTracker(i, &dropped));
+    }
+
+    arena.truncate(7);
+    assert!(dropped.borrow().is_empty());
+
+    arena.truncate(3);
+    assert_eq!(arena.len(), 3);
+    assert_eq!(*dropped.borrow(), vec![3, 4, 5, 6]);
+
+    arena.alloc(OrderTracker(7, &dropped));
+    let remaining: Vec<u32> = arena.iter_mut().map(|t| t.0).collect();
+    assert_eq!(remaining, vec![0, 1, 2, 7]);
+}
+
+#[test]
+fn truncate_rev_drops_back_to_front() {
+    let dropped = RefCell::new(Vec::new());
+    let mut arena = Arena::with_capacity(2); // force multiple inner vecs
+    for i in 0..7 {
+        arena.alloc(OrderTracker(i, &dropped));
+    }
+
+    arena.truncate_rev(10);
+    assert!(dropped.borrow().is_empty());
+
+    arena.truncate_rev(3);
+    assert_eq!(arena.len(), 3);
+    assert_eq!(*dropped.borrow(), vec![6, 5, 4, 3]);
+
+    arena.truncate_rev(0);
+    assert_eq!(arena.len(), 0);
+    assert_eq!(*dropped.borrow(), vec![6, 5, 4, 3, 2, 1, 0]);
+}
+
+#[test]
+fn iter_mut_is_send() {
+    fn assert_is_send<T: Send>(_: T) {}
+
+    // If `T` is `Send`, ...
+    assert_is_send(42_u32);
+
+    // Then `IterMut<T>` is also `Send`.
+    let mut arena: Arena<u32> = Arena::new();
+    assert_is_send(arena.iter_mut());
+}
+
+#[test]
+fn get_range_mut() {
+    let mut arena = Arena::with_capacity(2); // force multiple inner vecs
+    for i in 0..6 {
+        arena.alloc(i);
+    }
+
+    {
+        let middle = arena.get_range_mut(2..5).unwrap();
+        assert_eq!(middle, [2, 3, 4]);
+        middle[0] = 20;
+    }
+    assert_eq!(arena.get_range_mut(0..6).unwrap(), [0, 1, 20, 3, 4, 5]);
+    assert_eq!(arena.get_range_mut(6..6).unwrap(), []);
+    assert_eq!(arena.get_range_mut(4..7), None);
+}
+
+#[test]
+fn get_range_mut_only_moves_values_across_chunks() {
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for i in 0..6 {
+        arena.alloc(i);
+    }
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+
+    // Ranges within one chunk are sliced in place.
+    assert_eq!(arena.get_range_mut(0..2).unwrap(), [0, 1]);
+    assert_eq!(arena.get_range_mut(3..6).unwrap(), [3, 4, 5]);
+    assert_eq!(arena.get_range_mut(2..2).unwrap(), []);
+    assert_eq!(arena.get_range_mut(5..7), None);
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+
+    // A range across the chunk boundary needs a single chunk.
+    assert_eq!(arena.get_range_mut(1..3).unwrap(), [1, 2]);
+    assert!(arena.chunks.borrow().rest.is_empty());
+}
+
+#[test]
+fn push_within_capacity() {
+    let arena = Arena::with_capacity(2);
+    let a = arena.push_within_capacity(String::from("a")).unwrap();
+    let b = arena.push_within_capacity(String::from("b")).unwrap();
+    assert_eq!(
+        arena.push_within_capacity(String::from("c")),
+        Err(String::from("c"))
+    );
+    assert_eq!(arena.len(), 2);
+    assert_eq!(arena.chunks.borrow().rest.len(), 0);
+
+    arena.reserve_extend(1);
+    let c = arena.push_within_capacity(String::from("c")).unwrap();
+    assert_eq!((a.as_str(), b.as_str(), c.as_str()), ("a", "b", "c"));
+    assert_eq!(arena.len(), 3);
+}
+
+#[test]
+fn extend_from_within() {
+    let mut arena = Arena::with_capacity(4);
+    for s in &["a", "b", "c", "d"] {
+        arena.alloc(s.to_string());
+    }
+
+    {
+        let copies = arena.extend_from_within(0..2);
+        assert_eq!(copies, ["a", "b"]);
+        copies[0].push('!');
+    }
+    arena.extend_from_within(4..4);
+
+    assert_eq!(arena.into_vec(), vec!["a", "b", "c", "d", "a!", "b"]);
+}
+
+#[test]
+fn extend_from_within_one_chunk_keeps_chunks() {
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    arena.alloc_extend(0..2);
+    arena.alloc_extend(2..4);
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+
+    assert_eq!(arena.extend_from_within(2..4), [2, 3]);
+    // The values were cloned in place, not moved into a single chunk first.
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+
+    assert_eq!(arena.extend_from_within(1..3), [1, 2]);
+    assert_eq!(arena.into_vec(), vec![0, 1, 2, 3, 2, 3, 1, 2]);
+}
+
+#[test]
+#[should_panic]
+fn extend_from_within_out_of_bounds() {
+    let mut arena = Arena::new();
+    arena.alloc_extend(0..4);
+    arena.extend_from_within(2..5);
+}
+
+#[test]
+fn tree_with_parent_references() {
+    struct TreeNode<'a> {
+        parent: Option<&'a TreeNode<'a>>,
+        depth: u32,
+    }
+
+    const DEPTH: u32 = 6;
+
+    let arena = Arena::with_capacity(2); // force multiple inner vecs
+    let root: &TreeNode = arena.alloc(TreeNode {
+        parent: None,
+        depth: 0,
+    });
+
+    // Build a full binary tree, level by level.
+    let mut leaves = vec![root];
+    for _ in 0..DEPTH {
+        let mut next = Vec::new();
+        for parent in leaves {
+            for _ in 0..2 {
+                let child: &TreeNode = arena.alloc(TreeNode {
+                    parent: Some(parent),
+                    depth: parent.depth + 1,
+                });
+                next.push(child);
+            }
+        }
+        leaves = next;
+    }
+
+    assert_eq!(leaves.len(), 1 << DEPTH);
+    assert_eq!(arena.len(), (1 << (DEPTH + 1)) - 1);
+    for leaf in leaves {
+        let mut node = leaf;
+        let mut steps = 0;
+        while let Some(parent) = node.parent {
+            assert_eq!(parent.depth + 1, node.depth);
+            node = parent;
+            steps += 1;
+        }
+        assert_eq!(steps, DEPTH);
+        assert!(ptr::eq(node, root));
+    }
+}
+
+#[test]
+fn iter_mut_by_ref() {
+    let mut arena = Arena::with_capacity(2); // force multiple inner vecs
+    for i in 0..10 {
+        arena.alloc(i);
+    }
+
+    let mut iter = arena.iter_mut();
+    let first: Vec<i32> = iter.by_ref().take(3).map(|x| *x).collect();
+    let rest: Vec<i32> = iter.map(|x| *x).collect();
+
+    assert_eq!(first, vec![0, 1, 2]);
+    assert_eq!(rest, (3..10).collect::<Vec<_>>());
+}
+
+#[test]
+fn alloc_str_span() {
+    let mut arena = Arena::with_capacity(4);
+    let (first, _) = arena.alloc_str_span("abc");
+    // Doesn't fit into the current chunk anymore.
+    let (second, s) = arena.alloc_str_span("defgh");
+    assert_eq!(s, "defgh");
+    let (empty, _) = arena.alloc_str_span("");
+
+    assert_eq!(first, 0..3);
+    assert_eq!(second, 3..8);
+    assert_eq!(empty, 8..8);
+
+    let bytes = arena.as_mut_slice();
+    assert_eq!(&bytes[first], b"abc");
+    assert_eq!(&bytes[second], b"defgh");
+}
+
+#[test]
+fn iter_mut_rev() {
+    let mut arena = Arena::with_capacity(2); // force multiple inner vecs
+    for i in 0..5 {
+        arena.alloc(i);
+    }
+
+    let reversed: Vec<i32> = arena.iter_mut().rev().map(|x| *x).collect();
+    assert_eq!(reversed, vec![4, 3, 2, 1, 0]);
+
+    // Both ends can be consumed at the same time without yielding a value twice.
+    let mut iter = arena.iter_mut();
+    assert_eq!(iter.next(), Some(&mut 0));
+    assert_eq!(iter.next_back(), Some(&mut 4));
+    assert_eq!(iter.size_hint(), (3, Some(3)));
+    let middle: Vec<i32> = iter.map(|x| *x).collect();
+    assert_eq!(middle, vec![1, 2, 3]);
+}
+
+#[test]
+fn alloc_guard() {
+    let drop_counter = Cell::new(0);
+    {
+        let arena = Arena::with_capacity(1);
+
+        // Dropping the guard without committing doesn't allocate anything.
+        let slot = arena.alloc_guard().slot().as_mut_ptr();
+        assert_eq!(arena.len(), 0);
+
+        let mut guard = arena.alloc_guard();
+        assert_eq!(guard.slot().as_mut_ptr(), slot);
+        let first = unsafe {
+            ptr::write(
+                guard.slot().as_mut_ptr(),
+                Node(None, 1, DropTracker(&drop_counter)),
+            );
+            guard.commit()
+        };
+        assert_eq!(arena.len(), 1);
+
+        // The current chunk is full, so the next guard gets a new one.
+        let mut guard = arena.alloc_guard();
+        let second = unsafe {
+            ptr::write(
+                guard.slot().as_mut_ptr(),
+                Node(Some(first), 2, DropTracker(&drop_counter)),
+            );
+            guard.commit()
+        };
+        assert_eq!(arena.len(), 2);
+        assert_eq!(arena.chunks.borrow().rest.len(), 1);
+        assert_eq!(second.0.unwrap().1, 1);
+        assert_eq!(drop_counter.get(), 0);
+    }
+    assert_eq!(drop_counter.get(), 2);
+}
+
+#[test]
+#[should_panic]
+fn alloc_guard_locks_the_arena() {
+    let arena = Arena::new();
+    let _guard = arena.alloc_guard();
+    arena.alloc(1);
+}
+
+#[test]
+fn iter_mut_count() {
+    let mut arena = Arena::with_capacity(2); // force multiple inner vecs
+    for i in 0..10 {
+        arena.alloc(i);
+    }
+
+    assert_eq!(arena.iter_mut().count(), arena.len());
+    assert_eq!(arena.iter_mut().len(), arena.len());
+
+    let mut iter = arena.iter_mut();
+    iter.next();
+    iter.next_back();
+    assert_eq!(iter.len(), 8);
+    assert_eq!(iter.count(), 8);
+}
+
+#[test]
+fn alloc_bytes() {
+    let arena = Arena::with_capacity(4);
+    let a = arena.alloc_bytes(&[0xff, 0xfe]);
+    // Doesn't fit into the current chunk anymore.
+    let b = arena.alloc_bytes(&[0x80, 0x00, 0xc3, 0x28]);
+    let c = arena.alloc_bytes(&[]);
+
+    assert_eq!(a, [0xff, 0xfe]);
+    assert_eq!(b, [0x80, 0x00, 0xc3, 0x28]);
+    assert!(c.is_empty());
+    assert_eq!(arena.len(), 6);
+    assert_eq!(arena.into_vec(), vec![0xff, 0xfe, 0x80, 0x00, 0xc3, 0x28]);
+}
+
+#[test]
+fn eq_iter() {
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    arena.alloc_extend(0..10);
+    assert!(!arena.chunks.borrow().rest.is_empty());
+
+    assert!(arena.eq_iter(0..10));
+    assert!(!arena.eq_iter(0..9));
+    assert!(!arena.eq_iter(0..11));
+    assert!(!arena.eq_iter((0..10).map(|i| if i == 7 { 0 } else { i })));
+
+    let mut empty = Arena::<u32>::new();
+    assert!(empty.eq_iter(None));
+    assert!(!empty.eq_iter(Some(0)));
+}
+
+#[test]
+fn drain_into() {
+    let drop_counter = Cell::new(0);
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    arena.alloc_extend((0..10).map(|i| (i, DropTracker(&drop_counter))));
+    assert!(!arena.chunks.borrow().rest.is_empty());
+
+    let mut received = Vec::new();
+    arena.drain_into(|(i, _tracker)| received.push(i));
+
+    assert_eq!(received, (0..10).collect::<Vec<_>>());
+    assert_eq!(drop_counter.get(), 10);
+    assert_eq!(arena.len(), 0);
+
+    // The arena can be used again afterwards.
+    arena.alloc((10, DropTracker(&drop_counter)));
+    assert_eq!(arena.len(), 1);
+    drop(arena);
+    assert_eq!(drop_counter.get(), 11);
+}
+
+#[test]
+fn drain_into_with_panic() {
+    let drop_counter = Cell::new(0);
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    arena.alloc_extend((0..10).map(|i| (i, DropTracker(&drop_counter))));
+    assert!(!arena.chunks.borrow().rest.is_empty());
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        arena.drain_into(|(i, _tracker)| {
+            if i == 3 {
+                panic!("sink failed");
+            }
+        });
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(drop_counter.get(), 10);
+    assert_eq!(arena.len(), 0);
+}
+
+#[test]
+fn shrink_to_fit() {
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for i in 0..10 {
+        arena.alloc(i);
+    }
+    assert!(arena.chunks.borrow().rest.len() >= 2);
+
+    arena.truncate(2);
+    arena.shrink_to_fit();
+
+    {
+        let chunks = arena.chunks.borrow();
+        assert!(chunks.rest.is_empty());
+        assert_eq!(chunks.current.len(), 2);
+        assert_eq!(chunks.current.capacity(), 2);
+    }
+
+    // Allocating afterwards starts a new chunk.
+    arena.alloc(10);
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+    assert_eq!(arena.into_vec(), vec![0, 1, 10]);
+}
+
+#[test]
+fn shrink_to_fit_after_retain() {
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for i in 0..20 {
+        arena.alloc(i);
+    }
+
+    arena.retain(|&i| i == 1 || i == 19);
+    arena.shrink_to_fit();
+
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+    assert_eq!(arena.into_vec(), vec![1, 19]);
+}
+
+#[test]
+fn alloc_starts_new_chunk_when_full() {
+    let arena = Arena::with_capacity(2);
+    let a = arena.alloc(1);
+    let b = arena.alloc(2);
+    assert!(arena.chunks.borrow().rest.is_empty());
+
+    // The current chunk is full, so this goes through the slow path.
+    let c = arena.alloc(3);
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+
+    assert_eq!((*a, *b, *c), (1, 2, 3));
+    assert_eq!(arena.into_vec(), vec![1, 2, 3]);
+}
+
+#[test]
+fn collect_into() {
+    let drop_counter = Cell::new(0);
+    let mut all = Vec::new();
+    {
+        let first = Arena::with_capacity(2);
+        // force multiple inner vecs
+        first.alloc_extend((0..5).map(|i| (i, DropTracker(&drop_counter))));
+        let second = Arena::new();
+        second.alloc_extend((5..8).map(|i| (i, DropTracker(&drop_counter))));
+
+        first.collect_into(&mut all);
+        second.collect_into(&mut all);
+    }
+
+    // The values were moved, not dropped along with the arenas.
+    assert_eq!(drop_counter.get(), 0);
+    assert_eq!(
+        all.iter().map(|&(i, _)| i).collect::<Vec<_>>(),
+        (0..8).collect::<Vec<_>>()
+    );
+
+    drop(all);
+    assert_eq!(drop_counter.get(), 8);
+}
+
+#[test]
+fn max_and_min_by_key() {
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    arena.alloc_extend(vec![(3, 'a'), (7, 'b'), (1, 'c'), (7, 'd'), (1, 'e')]);
+    assert!(!arena.chunks.borrow().rest.is_empty());
+
+    // Ties go to the last maximum and the first minimum, like with iterators.
+    assert_eq!(arena.max_by_key(|&(k, _)| k), Some(&mut (7, 'd')));
+    assert_eq!(arena.min_by_key(|&(k, _)| k), Some(&mut (1, 'c')));
+    assert_eq!(
+        arena.iter_mut().max_by_key(|&&mut (k, _)| k),
+        Some(&mut (7, 'd'))
+    );
+
+    arena.max_by_key(|&(k, _)| k).unwrap().1 = 'z';
+    assert_eq!(arena.into_vec()[3], (7, 'z'));
+
+    let mut empty = Arena::<u32>::new();
+    assert_eq!(empty.max_by_key(|&x| x), None);
+    assert_eq!(empty.min_by_key(|&x| x), None);
+}
+
+#[test]
+fn retain_indexed() {
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    arena.alloc_extend((0..20).map(|i| i * 10));
+    assert!(!arena.chunks.borrow().rest.is_empty());
+
+    let mut seen = Vec::new();
+    arena.retain_indexed(|i, &value| {
+        seen.push(i);
+        assert_eq!(value, i * 10);
+        i % 2 == 1
+    });
+
+    // Indices are the original ones, even after earlier values were removed.
+    assert_eq!(seen, (0..20).collect::<Vec<_>>());
+    assert_eq!(
+        arena.into_vec(),
+        (0..10).map(|i| (2 * i + 1) * 10).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn get_back() {
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for i in 0..10 {
+        arena.alloc(i);
+    }
+    assert!(!arena.chunks.borrow().rest.is_empty());
+
+    assert_eq!(arena.get_back(0), Some(&mut 9));
+    assert_eq!(arena.get_back(1), Some(&mut 8));
+    assert_eq!(arena.get_back(9), Some(&mut 0));
+    assert_eq!(arena.get_back(10), None);
+
+    *arena.get_back(1).unwrap() = 80;
+    assert_eq!(arena.into_vec()[8], 80);
+
+    // The current chunk may be empty after emptying it from the back.
+    let mut arena = Arena::with_capacity(2);
+    arena.alloc_extend(0..3);
+    arena.truncate_rev(2);
+    assert_eq!(arena.get_back(0), Some(&mut 1));
+}
+
+#[test]
+fn alloc_extend_indexed() {
+    let mut arena = Arena::with_capacity(2);
+    let (first_start, first) = arena.alloc_extend_indexed(0..3);
+    assert_eq!(first_start, 0);
+    assert_eq!(first, [0, 1, 2]);
+    let first_len = first.len();
+
+    // force multiple inner vecs
+    let (second_start, second) = arena.alloc_extend_indexed(3..8);
+    assert_eq!(second_start, first_len);
+    assert_eq!(second, [3, 4, 5, 6, 7]);
+    assert!(!arena.chunks.borrow().rest.is_empty());
+
+    assert_eq!(
+        arena.get_range_mut(second_start..second_start + 5),
+        Some(&mut [3, 4, 5, 6, 7][..])
+    );
+}
+
+/// The spare capacity of the current chunk is uninitialized, so the slice views must stop at the
+/// last value. Under Miri, reading any further would be reported as undefined behavior.
+#[test]
+fn slices_do_not_cover_spare_capacity() {
+    let mut arena = Arena::with_capacity(100);
+    arena.alloc_extend(vec![true, false, true]);
+
+    assert_eq!(arena.as_mut_slice(), [true, false, true]);
+    assert_eq!(arena.as_cell_slice().len(), 3);
+    assert_eq!(
+        arena.get_range_mut(0..3),
+        Some(&mut [true, false, true][..])
+    );
+    assert_eq!(arena.get_range_mut(0..4), None);
+    assert_eq!(arena.iter_mut().count(), 3);
+    assert!(arena.chunks.borrow().current.capacity() >= 100);
+}
+
+#[test]
+fn is_empty() {
+    let mut arena = Arena::with_capacity(2);
+    assert!(arena.is_empty());
+
+    // force multiple inner vecs
+    for i in 0..5 {
+        arena.alloc(i);
+    }
+    assert!(!arena.is_empty());
+
+    // Only the earlier chunks hold values.
+    arena.truncate_rev(2);
+    assert!(arena.chunks.borrow().current.is_empty());
+    assert!(!arena.is_empty());
+
+    arena.truncate(0);
+    assert!(arena.is_empty());
+    assert!(!arena.chunks.borrow().rest.is_empty());
+}
+
+#[test]
+fn capacity() {
+    let arena = Arena::with_capacity(4);
+    assert_eq!(arena.capacity(), 4);
+
+    // Fill up the current chunk without allocating.
+    while arena.len() < arena.capacity() {
+        arena.push_within_capacity(0).unwrap();
+    }
+    assert!(arena.push_within_capacity(0).is_err());
+
+    // force multiple inner vecs
+    arena.alloc(1);
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+    let spare = {
+        let chunks = arena.chunks.borrow();
+        chunks.current.capacity() - chunks.current.len()
+    };
+    assert_eq!(arena.capacity(), 5 + spare);
+    assert_eq!(arena.remaining_capacity(), spare);
+}
+
+#[test]
+fn remaining_capacity() {
+    let arena = Arena::with_capacity(3);
+    assert_eq!(arena.remaining_capacity(), 3);
+
+    arena.alloc_extend(0..2);
+    assert_eq!(arena.remaining_capacity(), 1);
+    assert_eq!(arena.remaining_capacity(), arena.capacity() - arena.len());
+
+    arena.push_within_capacity(2).unwrap();
+    assert_eq!(arena.remaining_capacity(), 0);
+    assert!(arena.push_within_capacity(3).is_err());
+}
+
+#[test]
+fn is_full() {
+    let arena = Arena::with_capacity(3);
+    assert!(!arena.is_full());
+
+    arena.alloc_extend(0..2);
+    assert!(!arena.is_full());
+
+    // Fill the arena exactly to capacity.
+    arena.alloc(2);
+    assert!(arena.is_full());
+    assert_eq!(arena.len(), arena.capacity());
+    assert!(arena.push_within_capacity(3).is_err());
+
+    // The next allocation starts a new chunk with room to spare.
+    arena.alloc(3);
+    assert!(!arena.is_full());
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+}
+
+#[test]
+fn debug() {
+    struct NotDebug(#[allow(dead_code)] u32);
+
+    let arena = Arena::with_capacity(2);
+    arena.alloc(NotDebug(1));
+    assert_eq!(format!("{:?}", arena), "Arena { len: 1, capacity: 2 }");
+
+    let mut arena = Arena::with_capacity(2);
+    arena.alloc_extend(vec!["a", "b"]);
+    assert_eq!(format!("{:?}", arena), "Arena { len: 2, capacity: 2 }");
+    assert_eq!(format!("{:?}", arena.as_mut_slice()), r#"["a", "b"]"#);
+}
+
+#[test]
+fn debug_full() {
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    arena.alloc_extend(vec!["a", "b", "c"]);
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+
+    assert_eq!(format!("{:?}", arena), "Arena { len: 3, capacity: 4 }");
+    assert_eq!(
+        format!("{:?}", arena.debug_full()),
+        r#"Arena { len: 3, capacity: 4, values: ["a", "b", "c"] }"#
+    );
+    // The values are listed without moving them into a single chunk.
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+
+    let mut arena: Arena<u32> = Arena::with_capacity(2);
+    assert_eq!(format!("{:?}", arena), "Arena { len: 0, capacity: 2 }");
+    assert_eq!(
+        format!("{:?}", arena.debug_full()),
+        "Arena { len: 0, capacity: 2, values: [] }"
+    );
+}
+
+#[test]
+fn debug_while_locked() {
+    let arena = Arena::with_capacity(4);
+    arena.alloc_str("ab");
+
+    let writer = arena.str_writer();
+    assert_eq!(format!("{:?}", arena), "Arena { <locked> }");
+    writer.finish();
+
+    let guard = arena.alloc_guard();
+    assert_eq!(format!("{:?}", arena), "Arena { <locked> }");
+    drop(guard);
+
+    assert_eq!(format!("{:?}", arena), "Arena { len: 2, capacity: 4 }");
+}
+
+#[test]
+fn get_mut() {
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for i in 0..10 {
+        arena.alloc(i);
+    }
+    let chunk_count = arena.chunks.borrow().rest.len();
+    assert!(chunk_count > 0);
+
+    for i in 0..10 {
+        assert_eq!(arena.get_mut(i).copied(), Some(i));
+    }
+    assert_eq!(arena.get_mut(10), None);
+
+    *arena.get_mut(5).unwrap() = 50;
+    // The values weren't moved into one chunk.
+    assert_eq!(arena.chunks.borrow().rest.len(), chunk_count);
+
+    // Chunks emptied by `retain` are skipped.
+    arena.retain(|&i| i >= 4);
+    assert_eq!(arena.get_mut(0), Some(&mut 4));
+    assert_eq!(arena.get_mut(1), Some(&mut 50));
+    assert_eq!(arena.get_mut(6), None);
+}
+
+#[test]
+fn into_iter() {
+    let arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for i in 0..10 {
+        arena.alloc(i);
+    }
+    assert!(!arena.chunks.borrow().rest.is_empty());
+
+    let mut iter = arena.into_iter();
+    assert_eq!(iter.len(), 10);
+    assert_eq!(iter.next(), Some(0));
+    assert_eq!(iter.next_back(), Some(9));
+    assert_eq!(iter.len(), 8);
+    assert_eq!(iter.collect::<Vec<_>>(), (1..9).collect::<Vec<_>>());
+}
+
+#[test]
+fn into_iter_drops_unconsumed_values() {
+    let drop_counter = Cell::new(0);
+    let arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for _ in 0..10 {
+        arena.alloc(DropTracker(&drop_counter));
+    }
+
+    let mut iter = arena.into_iter();
+    for _ in 0..3 {
+        drop(iter.next());
+    }
+    assert_eq!(drop_counter.get(), 3);
+
+    drop(iter);
+    assert_eq!(drop_counter.get(), 10);
+}
+
+#[test]
+fn clear_keeps_memory_for_reuse() {
+    let drop_counter = Cell::new(0);
+    let mut arena = Arena::with_capacity(2);
+    for round in 1..4 {
+        // force multiple inner vecs in the first round
+        for _ in 0..10 {
+            arena.alloc(DropTracker(&drop_counter));
+        }
+        let capacity = arena.chunks.borrow().current.capacity();
+
+        arena.clear();
+
+        assert_eq!(drop_counter.get(), round * 10);
+        assert!(arena.is_empty());
+        let chunks = arena.chunks.borrow();
+        assert!(chunks.rest.is_empty());
+        assert_eq!(chunks.current.capacity(), capacity);
+    }
+
+    // By now the kept chunk has grown big enough for a whole round.
+    arena.alloc_extend((0..10).map(|_| DropTracker(&drop_counter)));
+    assert!(arena.chunks.borrow().rest.is_empty());
+}
+
+#[test]
+fn pool_reuses_cleared_arena() {
+    let drop_counter = Cell::new(0);
+    let mut pool = Pool::with_capacity(2);
+
+    let capacity = {
+        let arena = pool.acquire();
+        assert!(arena.is_empty());
+        // force multiple inner vecs
+        for _ in 0..10 {
+            arena.alloc(DropTracker(&drop_counter));
+        }
+        assert_eq!(drop_counter.get(), 0);
+        let capacity = arena.chunks.borrow().current.capacity();
+        capacity
+    };
+    // Releasing the arena dropped its values.
+    assert_eq!(drop_counter.get(), 10);
+
+    {
+        let arena = pool.acquire();
+        assert!(arena.is_empty());
+        assert_eq!(arena.capacity(), capacity);
+        assert!(arena.chunks.borrow().rest.is_empty());
+
+        // The kept chunk is filled up without growing.
+        arena.alloc_extend((0..capacity).map(|_| DropTracker(&drop_counter)));
+        assert!(arena.chunks.borrow().rest.is_empty());
+        mem::forget(arena);
+    }
+    // A leaked guard doesn't clear the arena, but the next `acquire` does.
+    assert_eq!(drop_counter.get(), 10);
+    let arena = pool.acquire();
+    assert_eq!(drop_counter.get(), 10 + capacity as u32);
+    assert!(arena.is_empty());
+    assert_eq!(arena.capacity(), capacity);
+}
+
+#[test]
+fn from_iter() {
+    let arena: Arena<u32> = (0..1000).collect();
+    assert_eq!(arena.len(), 1000);
+    // The size hint is used to allocate a single chunk.
+    assert!(arena.chunks.borrow().rest.is_empty());
+    assert_eq!(arena.into_vec(), (0..1000).collect::<Vec<_>>());
+
+    // Also works without a useful size hint.
+    let arena: Arena<u32> = (0..1000).filter(|i| i % 2 == 0).collect();
+    assert_eq!(arena.len(), 500);
+    assert_eq!(
+        arena.into_vec(),
+        (0..500).map(|i| i * 2).collect::<Vec<_>>()
+    );
+
+    let arena: Arena<u32> = iter::empty().collect();
+    assert!(arena.is_empty());
+}
+
+#[test]
+fn extend_through_shared_reference() {
+    let arena = Arena::with_capacity(2);
+    let first = arena.alloc(0);
+
+    // force multiple inner vecs
+    (&arena).extend(1..10);
+    let mut shared = &arena;
+    shared.extend(vec![10, 11]);
+
+    // Earlier references stay valid.
+    assert_eq!(*first, 0);
+    assert_eq!(arena.into_vec(), (0..12).collect::<Vec<_>>());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn from_par_iter() {
+    use rayon::prelude::*;
+
+    let mut arena: Arena<u32> = (0..10_000).into_par_iter().map(|i| i * 3).collect();
+    assert_eq!(arena.len(), 10_000);
+    assert!(arena.eq_iter((0..10_000).map(|i| i * 3)));
+
+    // The collected values form one chunk, and later values go into new ones.
+    assert!(arena.chunks.borrow().rest.is_empty());
+    arena.alloc(1);
+    assert_eq!(arena.chunks.borrow().rest.len(), 1);
+    assert!(arena.eq_iter((0..10_000).map(|i| i * 3).chain(Some(1))));
+}
+
+#[test]
+fn adjacency_lists_of_arena_references() {
+    struct GraphNode<'a> {
+        id: u32,
+        neighbors: Cell<&'a [&'a GraphNode<'a>]>,
+    }
+
+    let nodes = Arena::new();
+    let edges = Arena::with_capacity(2);
+
+    let a: &GraphNode = nodes.alloc(GraphNode {
+        id: 0,
+        neighbors: Cell::new(&[]),
+    });
+    let b: &GraphNode = nodes.alloc(GraphNode {
+        id: 1,
+        neighbors: Cell::new(&[]),
+    });
+    let c: &GraphNode = nodes.alloc(GraphNode {
+        id: 2,
+        neighbors: Cell::new(&[]),
+    });
+
+    a.neighbors.set(edges.alloc_slice_copy(&[b, c]));
+    // force multiple inner vecs
+    b.neighbors.set(edges.alloc_slice_copy(&[c, a, c]));
+    c.neighbors.set(edges.alloc_slice_copy(&[a]));
+
+    let ids = |node: &GraphNode| {
+        node.neighbors
+            .get()
+            .iter()
+            .map(|n| n.id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(a), vec![1, 2]);
+    assert_eq!(ids(b), vec![2, 0, 2]);
+    assert_eq!(ids(c), vec![0]);
+
+    // Walk a cycle through the graph.
+    let mut node = a;
+    for &expected in &[0, 1, 2, 0] {
+        assert_eq!(node.id, expected);
+        node = node.neighbors.get()[0];
+    }
+    assert_eq!(edges.len(), 6);
+}
+
+#[test]
+fn alloc_slice_copy() {
+    let arena = Arena::with_capacity(4);
+    let a = arena.alloc_slice_copy(&[1, 2, 3]);
+    // Doesn't fit into the current chunk, so the whole slice goes into a new one.
+    let b = arena.alloc_slice_copy(&[4, 5, 6]);
+    let empty = arena.alloc_slice_copy(&[]);
+
+    assert_eq!(a, [1, 2, 3]);
+    assert_eq!(b, [4, 5, 6]);
+    assert!(empty.is_empty());
+    {
+        let chunks = arena.chunks.borrow();
+        assert_eq!(chunks.rest.len(), 1);
+        assert_eq!(chunks.rest[0].len(), 3);
+        assert_eq!(chunks.current.len(), 3);
+    }
+
+    // The copies are independent of the source and of each other.
+    a[0] = 10;
+    b[0] = 40;
+    assert_eq!(arena.into_vec(), vec![10, 2, 3, 40, 5, 6]);
+}
+
+#[test]
+fn alloc_slice_clone() {
+    let arena = Arena::with_capacity(2);
+    arena.alloc(String::from("first"));
+    let src = vec![String::from("a"), String::from("b"), String::from("c")];
+
+    // force multiple inner vecs
+    let clones = arena.alloc_slice_clone(&src);
+    assert_eq!(clones, ["a", "b", "c"]);
+    clones[0].push('!');
+
+    assert_eq!(src, ["a", "b", "c"]);
+    assert_eq!(arena.into_vec(), vec!["first", "a!", "b", "c"]);
+}
+
+/// Check nothing is dropped twice or leaked when cloning panics partway through a slice.
+#[test]
+fn alloc_slice_clone_with_panic() {
+    struct PanicOnClone<'a> {
+        panic: bool,
+        tracker: DropTracker<'a>,
+    }
+
+    impl<'a> Clone for PanicOnClone<'a> {
+        fn clone(&self) -> Self {
+            if self.panic {
+                panic!("clone failed");
+            }
+            PanicOnClone {
+                panic: false,
+                tracker: DropTracker(self.tracker.0),
+            }
+        }
+    }
+
+    let drop_counter = Cell::new(0);
+    {
+        let src: Vec<_> = (0..4)
+            .map(|i| PanicOnClone {
+                panic: i == 2,
+                tracker: DropTracker(&drop_counter),
+            })
+            .collect();
+        let arena = Arena::with_capacity(2);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            arena.alloc_slice_clone(&src);
+        }));
+        assert!(result.is_err());
+
+        // The two clones made before the panic are still owned by the arena.
+        assert_eq!(arena.len(), 2);
+        assert_eq!(drop_counter.get(), 0);
+    }
+    // Four originals and two clones.
+    assert_eq!(drop_counter.get(), 6);
+}
+
+#[test]
+fn alloc_str_larger_than_remaining_space() {
+    let arena = Arena::with_capacity(8);
+    let hello = arena.alloc_str("hello");
+    assert_eq!(arena.remaining_capacity(), 3);
+
+    // One byte more than fits into the current chunk.
+    let s = arena.alloc_str("four");
+
+    assert_eq!(s, "four");
+    assert_eq!(hello, "hello");
+    let chunks = arena.chunks.borrow();
+    // None of the bytes went into the old chunk; the string was copied whole into a new one.
+    assert_eq!(chunks.rest.len(), 1);
+    assert_eq!(chunks.rest[0], b"hello");
+    assert_eq!(chunks.current, b"four");
+}
+
+#[test]
+fn alloc_fmt() {
+    let arena = Arena::with_capacity(4);
+    let a = arena.alloc_fmt(format_args!("{}", 12));
+    // Outgrows the current chunk partway through, which moves the start to a new chunk.
+    let b = arena.alloc_fmt(format_args!("{}-{}-{}", "ab", 345, 'c'));
+    let empty = arena.alloc_fmt(format_args!(""));
+
+    assert_eq!(a, "12");
+    assert_eq!(b, "ab-345-c");
+    assert_eq!(empty, "");
+    assert_eq!(arena.len(), 10);
+    {
+        let chunks = arena.chunks.borrow();
+        // Only the first string is left in the first chunk.
+        assert_eq!(chunks.rest[0], b"12");
+    }
+    assert_eq!(arena.into_vec(), b"12ab-345-c");
+}
+
+#[test]
+fn alloc_fmt_error() {
+    struct Failing;
+
+    impl fmt::Display for Failing {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("partial")?;
+            Err(fmt::Error)
+        }
+    }
+
+    let arena = Arena::with_capacity(4);
+    arena.alloc_str("ok");
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        arena.alloc_fmt(format_args!("{}", Failing));
+    }));
+
+    assert!(result.is_err());
+    // Nothing of the failed string is left behind.
+    assert_eq!(arena.into_vec(), b"ok");
+}
+
+#[test]
+fn str_writer() {
+    use core::fmt::Write;
+
+    let arena = Arena::with_capacity(4);
+    let before = arena.alloc_str("ab");
+
+    let mut writer = arena.str_writer();
+    // force multiple inner vecs
+    for i in 0..10 {
+        write!(writer, "{},", i).unwrap();
+    }
+    writer.write_str("end").unwrap();
+    let line = writer.finish();
+
+    assert_eq!(before, "ab");
+    assert_eq!(line, "0,1,2,3,4,5,6,7,8,9,end");
+    assert!(!arena.chunks.borrow().rest.is_empty());
+
+    // Text written without `finish` stays in the arena.
+    write!(arena.str_writer(), "!").unwrap();
+    assert_eq!(arena.len(), 2 + line.len() + 1);
+}
+
+#[test]
+#[should_panic]
+fn str_writer_locks_the_arena() {
+    let arena = Arena::new();
+    let _writer = arena.str_writer();
+    arena.alloc(1);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn io_write() {
+    use std::io::{self, Write};
+
+    let arena = Arena::with_capacity(4);
+    let mut sink = &arena;
+    assert_eq!(sink.write(b"abc").unwrap(), 3);
+    // force multiple inner vecs
+    write!(sink, "{}", 12345).unwrap();
+    sink.flush().unwrap();
+    assert_eq!(io::copy(&mut &b"xyz"[..], &mut sink).unwrap(), 3);
+
+    assert!(!arena.chunks.borrow().rest.is_empty());
+    assert_eq!(arena.into_vec(), b"abc12345xyz");
+}
+
+#[test]
+fn as_mut() {
+    fn fill<B: AsMut<[u8]>>(mut buffer: B, value: u8) {
+        for byte in buffer.as_mut() {
+            *byte = value;
+        }
+    }
+
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for i in 0..5 {
+        arena.alloc(i);
+    }
+    fill(&mut arena, 7);
+
+    assert_eq!(arena.into_vec(), vec![7; 5]);
+}
+
+#[test]
+fn iter_mut_after_into_vec_round_trip() {
+    let arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for i in 0..5 {
+        arena.alloc(i);
+    }
+    let values = arena.into_vec();
+    assert_eq!(values.len(), 5);
+
+    let mut arena: Arena<_> = values.into_iter().collect();
+    assert_eq!(arena.len(), 5);
+    arena.alloc_extend(5..8);
+    arena.alloc(8);
+    assert_eq!(arena.len(), 9);
+
+    let iter = arena.iter_mut();
+    assert_eq!(iter.len(), 9);
+    for value in iter {
+        *value *= 2;
+    }
+    assert!(arena.eq_iter((0..9).map(|i| i * 2)));
+    assert_eq!(
+        arena.iter_mut().rev().map(|v| *v).collect::<Vec<_>>(),
+        vec![16, 14, 12, 10, 8, 6, 4, 2, 0]
+    );
+}
+
+#[test]
+fn clone_arena_is_deep() {
+    #[derive(Clone)]
+    struct Tracked<'a>(String, &'a Cell<u32>);
+
+    impl<'a> Drop for Tracked<'a> {
+        fn drop(&mut self) {
+            self.1.set(self.1.get() + 1);
+        }
+    }
+
+    let drop_counter = Cell::new(0);
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for i in 0..5 {
+        arena.alloc(Tracked(i.to_string(), &drop_counter));
+    }
+
+    let mut copy = arena.clone_arena();
+    assert_eq!(copy.len(), 5);
+    assert!(copy.chunks.borrow().rest.is_empty());
+    assert_eq!(drop_counter.get(), 0);
+
+    // Changing a clone doesn't affect the original.
+    copy.get_mut(0).unwrap().0.push('!');
+    assert_eq!(copy.get_mut(0).unwrap().0, "0!");
+    assert_eq!(arena.get_mut(0).unwrap().0, "0");
+
+    drop(copy);
+    assert_eq!(drop_counter.get(), 5);
+    assert_eq!(arena.len(), 5);
+    drop(arena);
+    assert_eq!(drop_counter.get(), 10);
+}
+
+#[test]
+fn over_aligned_values() {
+    #[repr(align(64))]
+    struct Aligned(u8);
+
+    fn assert_aligned<T>(value: &T) {
+        assert_eq!(value as *const T as usize % mem::align_of::<T>(), 0);
+    }
+
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for i in 0..10 {
+        assert_aligned(arena.alloc(Aligned(i)));
+    }
+    for value in arena.alloc_extend((10..15).map(Aligned)) {
+        assert_aligned(value);
+    }
+    assert!(!arena.chunks.borrow().rest.is_empty());
+
+    for (i, value) in arena.iter_mut().enumerate() {
+        assert_aligned(value);
+        assert_eq!(value.0 as usize, i);
+    }
+    for value in arena.as_mut_slice() {
+        assert_aligned(value);
+    }
+}
+
+#[test]
+fn retain_calls_predicate_once_per_value_in_order() {
+    let mut arena = Arena::with_capacity(2);
+    // force multiple inner vecs
+    for i in 0..20 {
+        arena.alloc(i);
+    }
+    assert!(arena.chunks.borrow().rest.len() > 1);
+
+    let mut seen = Vec::new();
+    arena.retain(|&i| {
+        seen.push(i);
+        i % 3 != 0
+    });
+    assert_eq!(seen, (0..20).collect::<Vec<_>>());
+
+    // A second pass only sees the values that were kept, including after emptied chunks.
+    arena.retain(|&i| i >= 10);
+    seen.clear();
+    arena.retain(|&i| {
+        seen.push(i);
+        true
+    });
+    assert_eq!(seen, vec![10, 11, 13, 14, 16, 17, 19]);
+}
+
+#[test]
+fn truncate_reuses_space_in_current_chunk() {
+    let mut arena = Arena::with_capacity(4);
+    arena.alloc_extend(0..4);
+    assert_eq!(arena.remaining_capacity(), 0);
+
+    arena.truncate(2);
+    assert_eq!(arena.remaining_capacity(), 2);
+    arena.alloc_extend(4..6);
+    assert!(arena.chunks.borrow().rest.is_empty());
+
+    // force multiple inner vecs
+    arena.alloc(6);
+    arena.truncate(1);
+    // The space freed in the earlier chunk stays unused.
+    assert_eq!(arena.chunks.borrow().rest[0].len(), 1);
+    arena.alloc(7);
+    assert_eq!(arena.chunks.borrow().rest[0].len(), 1);
+    assert_eq!(arena.into_vec(), vec![0, 7]);
+}